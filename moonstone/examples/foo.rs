@@ -1,15 +1,14 @@
-use std::mem::{self, swap};
-
 use godot::{
-    classes::{Button, LineEdit, Node, PanelContainer, VBoxContainer},
-    obj::{Gd, WithBaseField},
+    classes::{Button, INode, Label, LineEdit, Node, VBoxContainer, control::TextDirection},
+    prelude::*,
 };
-use moonstone::{ViewValue, mutate, viewtype};
+use moonstone::{App, Element, Messenger, Program, el, mutate, viewtype};
 
 viewtype! {
     enum Guy {
         Foo(Gd<Button>),
         Bar(Gd<LineEdit>),
+        Nobody,
     }
 }
 
@@ -22,64 +21,116 @@ viewtype! {
     }
 }
 
-// impl CustomView for Bar {
-//     fn init(&mut self) {
-//         mutate!(self { a, b }, {
-//             swap(a, b);
-//         })
-//         // self.peeenis()
-//         // self.p
-//         // mutate(self).a().b().
-//     }
-// }
-use godot::prelude::*;
+#[godot_api]
+impl Bar {
+    /// Replaces both buttons, rebuilt together on the next frame.
+    #[func]
+    fn replace_buttons(&mut self) {
+        mutate!(self { a, b }, {
+            *a = Button::new_alloc();
+            *b = Button::new_alloc();
+        });
+    }
+
+    /// Replaces the first button right away.
+    #[func]
+    fn replace_first_now(&mut self) {
+        mutate!(sync self{a}, {
+            *a = Button::new_alloc();
+        });
+    }
+}
 
 #[derive(GodotClass)]
-#[class(base=Node, no_init)]
-struct Gang {
+#[class(base=Node, init)]
+struct Demo {
     base: Base<Node>,
+    guy: Option<App<Guy>>,
+    bar: Option<App<Gd<Bar>>>,
+    counter: Option<Program<u32, CounterMsg, Counter>>,
+}
+
+#[derive(Clone)]
+enum CounterMsg {
+    Increment,
+    Reset,
+}
+
+type Counter = Element<VBoxContainer, (Element<Label>, Element<Button>, Element<Button>)>;
+
+fn counter(count: &u32, messenger: &Messenger<CounterMsg>) -> Counter {
+    el::<VBoxContainer>().children((
+        el::<Label>().prop("text", format!("Clicked {count} times")),
+        el::<Button>()
+            .prop("text", "Click")
+            .set(Button::set_text_direction, TextDirection::RTL)
+            .on_pressed(messenger.callback(CounterMsg::Increment)),
+        el::<Button>()
+            .prop("text", "Reset")
+            .prop("disabled", *count == 0)
+            .on_pressed(messenger.callback(CounterMsg::Reset)),
+    ))
+}
+
+fn update_counter(count: &mut u32, msg: CounterMsg) {
+    match msg {
+        CounterMsg::Increment => *count += 1,
+        CounterMsg::Reset => *count = 0,
+    }
 }
-fn lol() {
-    Bar_Init {
-        a: todo!(),
-        lol: todo!(),
-        b: todo!(),
+
+#[godot_api]
+impl INode for Demo {
+    fn ready(&mut self) {
+        let mount = self.to_gd();
+        self.guy = Some(App::new(mount.clone(), || Guy::Foo(Button::new_alloc())));
+        self.counter = Some(Program::new(mount.clone(), 0, counter, update_counter));
+        self.bar = Some(App::new(mount, || {
+            Bar_Init {
+                a: Button::new_alloc(),
+                lol: Button::new_alloc(),
+                b: Button::new_alloc(),
+            }
+            .build(|_| {})
+        }));
+    }
+
+    fn process(&mut self, _delta: f64) {
+        if let Some(counter) = &mut self.counter {
+            counter.process();
+        }
+    }
+
+    fn exit_tree(&mut self) {
+        if let Some(guy) = self.guy.take() {
+            guy.destroy();
+        }
+        if let Some(bar) = self.bar.take() {
+            bar.destroy();
+        }
+        if let Some(counter) = self.counter.take() {
+            counter.destroy();
+        }
+    }
+}
+
+#[godot_api]
+impl Demo {
+    #[func]
+    fn toggle(&mut self) {
+        if let Some(guy) = &mut self.guy {
+            guy.update(|guy| {
+                *guy = match guy {
+                    Guy::Foo(_) => Guy::Bar(LineEdit::new_alloc()),
+                    Guy::Bar(_) => Guy::Nobody,
+                    Guy::Nobody => Guy::Foo(Button::new_alloc()),
+                }
+            });
+        }
     }
-    .build(|b| {});
-    // Gd::fro
-    // Bar_Init {
-    //     a: Button::new_alloc(),
-    //     b: Button::new_alloc(),
-    //     lol: |this|
-    // }
-    // Bar::build(|this| Bar_Init {
-    //     a: Button::new_alloc(),
-    //     b: Button::new_alloc(),
-    // });
-    // Gd::fro
 }
-// fn lol(v: Gang) {
-// }
 
-// struct Foo {
-//     v: String,
-// }
-// macro_rules! gog {
-//     ({
-//         $($t:tt)*
-//     }) => {
-//         {
-//             $($t)*
-//         }
-//     };
-// }
-// impl Foo {
-//     fn lol(&mut self, f: impl FnOnce(&mut String, &mut Self)) {
-//         // f(&mut self.v, self);
-//         let lol = gog!({
-//             let a = 3;
-//         });
-//     }
-// }
+struct FooExtension;
 
-fn main() {}
+#[gdextension]
+unsafe impl ExtensionLibrary for FooExtension {}
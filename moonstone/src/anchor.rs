@@ -0,0 +1,322 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+};
+
+use crate::{Backend, Godot, View};
+
+/// How a view that owns an anchor keeps track of its position among its
+/// siblings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AnchorStrategy {
+    /// A placeholder `Node` is inserted right after the view's nodes, and new
+    /// nodes are inserted in front of it.
+    #[default]
+    Marker,
+    /// No placeholder is inserted. The position is worked out from the nodes
+    /// of the siblings that come after the view.
+    Positional,
+}
+
+thread_local! {
+    static ANCHOR_STRATEGY: Cell<AnchorStrategy> = const { Cell::new(AnchorStrategy::Marker) };
+}
+
+/// Sets the strategy used by anchors created on this thread from now on.
+pub fn set_anchor_strategy(strategy: AnchorStrategy) {
+    ANCHOR_STRATEGY.set(strategy);
+}
+pub fn anchor_strategy() -> AnchorStrategy {
+    ANCHOR_STRATEGY.get()
+}
+
+/// The nodes and nested anchors added through one anchor, in tree order.
+#[doc(hidden)]
+pub struct Slot<B: Backend> {
+    parent: B::Node,
+    outer: Option<Rc<RefCell<Slot<B>>>>,
+    marker: Option<B::Node>,
+    entries: Vec<Entry<B>>,
+}
+enum Entry<B: Backend> {
+    Node(B::Node),
+    Slot(Weak<RefCell<Slot<B>>>),
+}
+
+impl<B: Backend> Entry<B> {
+    fn first_node(&self) -> Option<B::Node> {
+        match self {
+            Entry::Node(node) => Some(node.clone()),
+            Entry::Slot(slot) => slot.upgrade().and_then(|slot| slot.borrow().first_node()),
+        }
+    }
+    fn is_slot(&self, slot: &Rc<RefCell<Slot<B>>>) -> bool {
+        matches!(self, Entry::Slot(s) if Weak::as_ptr(s) == Rc::as_ptr(slot))
+    }
+}
+
+impl<B: Backend> Slot<B> {
+    fn first_node(&self) -> Option<B::Node> {
+        self.entries
+            .iter()
+            .find_map(Entry::first_node)
+            .or_else(|| self.marker.clone())
+    }
+
+    /// The node that anything added to `this` next is inserted in front of, or
+    /// `None` if it should be appended to the parent.
+    fn insertion_point(this: &Rc<RefCell<Slot<B>>>) -> Option<B::Node> {
+        let slot = this.borrow();
+        if let Some(marker) = &slot.marker {
+            return Some(marker.clone());
+        }
+        Slot::point_after(slot.outer.as_ref()?, this)
+    }
+
+    /// The first node after the nested slot `child` of `this`.
+    fn point_after(this: &Rc<RefCell<Slot<B>>>, child: &Rc<RefCell<Slot<B>>>) -> Option<B::Node> {
+        let slot = this.borrow();
+        if let Some(idx) = slot.entries.iter().rposition(|e| e.is_slot(child))
+            && let Some(node) = slot.entries[idx + 1..].iter().find_map(Entry::first_node)
+        {
+            return Some(node);
+        }
+        drop(slot);
+        Slot::insertion_point(this)
+    }
+}
+
+/// Moves `node`, which must already be a child of `parent`, in front of
+/// `before`, or to the end if there is no such node.
+pub(crate) fn place<B: Backend>(parent: &B::Node, node: &B::Node, before: Option<&B::Node>) {
+    let from = B::index(node);
+    match before {
+        Some(before) => {
+            let to = B::index(before);
+            if from + 1 != to {
+                B::move_child(parent, node, if from < to { to - 1 } else { to });
+            }
+        }
+        None => {
+            let last = B::child_count(parent) - 1;
+            if from != last {
+                B::move_child(parent, node, last);
+            }
+        }
+    }
+}
+
+pub trait Anchor<B: Backend = Godot> {
+    #[doc(hidden)]
+    fn slot(&self) -> &Rc<RefCell<Slot<B>>>;
+
+    /// Inserts `node` after everything else added through this anchor.
+    fn add(&mut self, node: &B::Node) {
+        let slot = self.slot();
+        let before = Slot::insertion_point(slot);
+        let parent = slot.borrow().parent.clone();
+        B::add_child(&parent, node);
+        place::<B>(&parent, node, before.as_ref());
+        slot.borrow_mut().entries.push(Entry::Node(node.clone()));
+    }
+
+    /// Removes `node` from the tree if it was added through this anchor.
+    fn remove(&mut self, node: &B::Node) {
+        let mut slot = self.slot().borrow_mut();
+        let idx = slot
+            .entries
+            .iter()
+            .rposition(|e| matches!(e, Entry::Node(n) if n == node));
+        if let Some(idx) = idx {
+            slot.entries.remove(idx);
+            B::remove_child(&slot.parent, node);
+        }
+    }
+
+    /// The node everything added through this anchor is a child of.
+    fn parent(&self) -> B::Node {
+        self.slot().borrow().parent.clone()
+    }
+}
+
+/// Adds nodes as the last children of a node.
+pub struct ChildAnchor<B: Backend = Godot> {
+    slot: Rc<RefCell<Slot<B>>>,
+}
+impl<B: Backend> ChildAnchor<B> {
+    pub fn new(node: B::Node) -> Self {
+        Self {
+            slot: Rc::new(RefCell::new(Slot {
+                parent: node,
+                outer: None,
+                marker: None,
+                entries: vec![],
+            })),
+        }
+    }
+}
+impl<B: Backend> Anchor<B> for ChildAnchor<B> {
+    fn slot(&self) -> &Rc<RefCell<Slot<B>>> {
+        &self.slot
+    }
+}
+
+/// Adds nodes in front of whatever comes after it in its parent anchor, so a
+/// view keeps its place among its siblings when its nodes change.
+pub struct BeforeAnchor<B: Backend = Godot> {
+    slot: Rc<RefCell<Slot<B>>>,
+}
+impl<B: Backend> BeforeAnchor<B> {
+    /// Creates an anchor at the end of `parent_anchor`, using the current
+    /// [`AnchorStrategy`].
+    pub fn attach(parent_anchor: &mut dyn Anchor<B>) -> Self {
+        Self::attach_with(parent_anchor, anchor_strategy())
+    }
+
+    pub fn attach_with(parent_anchor: &mut dyn Anchor<B>, strategy: AnchorStrategy) -> Self {
+        let outer = parent_anchor.slot().clone();
+        let parent = outer.borrow().parent.clone();
+        let marker = match strategy {
+            AnchorStrategy::Marker => {
+                let marker = B::new_marker();
+                let before = Slot::insertion_point(&outer);
+                B::add_child(&parent, &marker);
+                place::<B>(&parent, &marker, before.as_ref());
+                Some(marker)
+            }
+            AnchorStrategy::Positional => None,
+        };
+        let slot = Rc::new(RefCell::new(Slot {
+            parent,
+            outer: Some(outer.clone()),
+            marker,
+            entries: vec![],
+        }));
+        outer
+            .borrow_mut()
+            .entries
+            .push(Entry::Slot(Rc::downgrade(&slot)));
+        Self { slot }
+    }
+
+    /// Removes this anchor from `parent_anchor` and frees its marker node.
+    ///
+    /// Everything added through the anchor should have been removed already.
+    pub fn detach(&mut self, parent_anchor: &mut dyn Anchor<B>) {
+        parent_anchor
+            .slot()
+            .borrow_mut()
+            .entries
+            .retain(|e| !e.is_slot(&self.slot));
+        let mut slot = self.slot.borrow_mut();
+        if let Some(marker) = slot.marker.take() {
+            B::remove_child(&slot.parent, &marker);
+            B::free(&marker);
+        }
+    }
+
+    /// Pushes the marker node, if this anchor has one.
+    pub fn collect_nodes(&self, nodes: &mut Vec<B::Node>) {
+        if let Some(marker) = &self.slot.borrow().marker {
+            nodes.push(marker.clone());
+        }
+    }
+
+    /// Forgets every node and nested anchor added through this anchor, without
+    /// touching the tree.
+    pub(crate) fn forget_entries(&mut self) {
+        self.slot.borrow_mut().entries.clear();
+    }
+
+    /// The first node in this anchor's range.
+    pub(crate) fn first_node(&self) -> Option<B::Node> {
+        self.slot.borrow().first_node()
+    }
+
+    /// The node right after this anchor's range, or `None` if it ends its
+    /// parent's children.
+    pub(crate) fn end(&self) -> Option<B::Node> {
+        Slot::insertion_point(&self.slot)
+    }
+
+    /// Moves the nested anchor `child` in front of `next`, or to the end.
+    ///
+    /// Only updates the bookkeeping; the nodes themselves must be moved by the
+    /// caller.
+    pub(crate) fn move_nested(&mut self, child: &BeforeAnchor<B>, next: Option<&BeforeAnchor<B>>) {
+        let mut slot = self.slot.borrow_mut();
+        let Some(from) = slot.entries.iter().position(|e| e.is_slot(&child.slot)) else {
+            return;
+        };
+        let entry = slot.entries.remove(from);
+        let to = next
+            .and_then(|next| slot.entries.iter().position(|e| e.is_slot(&next.slot)))
+            .unwrap_or(slot.entries.len());
+        slot.entries.insert(to, entry);
+    }
+}
+impl<B: Backend> Anchor<B> for BeforeAnchor<B> {
+    fn slot(&self) -> &Rc<RefCell<Slot<B>>> {
+        &self.slot
+    }
+}
+
+/// Builds a view, and every view below it, with a specific [`AnchorStrategy`].
+pub struct Anchored<T> {
+    strategy: AnchorStrategy,
+    view: T,
+}
+
+pub fn anchored<T>(strategy: AnchorStrategy, view: T) -> Anchored<T> {
+    Anchored { strategy, view }
+}
+
+/// Builds `view` without any marker nodes, see [`AnchorStrategy::Positional`].
+pub fn fragment<T>(view: T) -> Anchored<T> {
+    anchored(AnchorStrategy::Positional, view)
+}
+
+/// Runs `f` with the anchors it creates using `strategy`.
+///
+/// Views that build or rebuild outside of their parent, such as from an
+/// effect, use it to keep the strategy they were built with.
+pub(crate) fn with_strategy<R>(strategy: AnchorStrategy, f: impl FnOnce() -> R) -> R {
+    struct Restore(AnchorStrategy);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            set_anchor_strategy(self.0);
+        }
+    }
+
+    let _restore = Restore(anchor_strategy());
+    set_anchor_strategy(strategy);
+    f()
+}
+
+impl<B: Backend, T: View<B>> View<B> for Anchored<T> {
+    type State = T::State;
+    type Access<'a>
+        = T::Access<'a>
+    where
+        T: 'a;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        with_strategy(self.strategy, || self.view.build(parent_anchor))
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        with_strategy(self.strategy, || self.view.rebuild(state));
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+        <T as View<B>>::teardown(state, parent_anchor);
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+        <T as View<B>>::collect_nodes(state, nodes);
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        self.view.access()
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    any::{Any, TypeId},
+    marker::PhantomData,
+    rc::Rc,
+    sync::Arc,
+};
+
+use crate::{Anchor, Backend, BeforeAnchor, Godot, View};
+
+/// An object-safe [`View`], so views of different types can be stored together
+/// as `Box<dyn AnyView>`.
+pub trait AnyView<B: Backend = Godot>: Any {
+    fn as_any(&self) -> &dyn Any;
+    fn dyn_build(&self, parent_anchor: &mut dyn Anchor<B>) -> Box<dyn AnyState<B>>;
+    /// Rebuilds `state`, which must have been built by a view of the same type.
+    fn dyn_rebuild(&self, state: &mut dyn AnyState<B>);
+}
+
+/// The type-erased state of an [`AnyView`].
+pub trait AnyState<B: Backend = Godot> {
+    fn teardown(&mut self, parent_anchor: &mut dyn Anchor<B>);
+    fn collect_nodes(&self, nodes: &mut Vec<B::Node>);
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+struct TypedState<B: Backend, T: View<B>>(T::State, PhantomData<B>);
+
+impl<B: Backend, T: View<B> + 'static> AnyState<B> for TypedState<B, T> {
+    fn teardown(&mut self, parent_anchor: &mut dyn Anchor<B>) {
+        <T as View<B>>::teardown(&mut self.0, parent_anchor);
+    }
+
+    fn collect_nodes(&self, nodes: &mut Vec<B::Node>) {
+        <T as View<B>>::collect_nodes(&self.0, nodes);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<B: Backend, T: View<B> + 'static> AnyView<B> for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn dyn_build(&self, parent_anchor: &mut dyn Anchor<B>) -> Box<dyn AnyState<B>> {
+        Box::new(TypedState::<B, T>(self.build(parent_anchor), PhantomData))
+    }
+
+    fn dyn_rebuild(&self, state: &mut dyn AnyState<B>) {
+        let state = state
+            .as_any_mut()
+            .downcast_mut::<TypedState<B, T>>()
+            .expect("AnyView state was built by a different view type");
+        self.rebuild(&mut state.0);
+    }
+}
+
+pub struct AnyViewState<B: Backend = Godot> {
+    anchor: BeforeAnchor<B>,
+    type_id: TypeId,
+    inner_state: Box<dyn AnyState<B>>,
+}
+impl<B: Backend> View<B> for Box<dyn AnyView<B>> {
+    type State = AnyViewState<B>;
+    type Access<'a> = &'a dyn AnyView<B>;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        let mut any_anchor = BeforeAnchor::attach(parent_anchor);
+
+        let inner_state = (**self).dyn_build(&mut any_anchor);
+
+        AnyViewState {
+            anchor: any_anchor,
+            type_id: (**self).as_any().type_id(),
+            inner_state,
+        }
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        let type_id = (**self).as_any().type_id();
+        if type_id == state.type_id {
+            (**self).dyn_rebuild(&mut *state.inner_state);
+        } else {
+            state.inner_state.teardown(&mut state.anchor);
+            state.inner_state = (**self).dyn_build(&mut state.anchor);
+            state.type_id = type_id;
+        }
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+        state.inner_state.teardown(&mut state.anchor);
+        state.anchor.detach(parent_anchor);
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+        state.inner_state.collect_nodes(nodes);
+        state.anchor.collect_nodes(nodes);
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        &**self
+    }
+}
+
+macro_rules! impl_view_pointer {
+    ($($ptr:ident),*) => {
+        $(
+            impl<B: Backend, T: View<B>> View<B> for $ptr<T> {
+                type State = T::State;
+                type Access<'a>
+                    = T::Access<'a>
+                where
+                    T: 'a;
+
+                fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+                    (**self).build(parent_anchor)
+                }
+
+                fn rebuild(&self, state: &mut Self::State) {
+                    (**self).rebuild(state);
+                }
+
+                fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+                    <T as View<B>>::teardown(state, parent_anchor);
+                }
+
+                fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+                    <T as View<B>>::collect_nodes(state, nodes);
+                }
+
+                fn access<'a>(&'a self) -> Self::Access<'a> {
+                    (**self).access()
+                }
+            }
+        )*
+    };
+}
+
+impl_view_pointer!(Box, Rc, Arc);
@@ -1,24 +1,168 @@
-use std::{cell::RefCell, collections::VecDeque, rc::Rc, sync::Arc};
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
 
 use godot::{
     classes::Node,
     obj::{Gd, Inherits},
 };
-use parking_lot::Mutex;
 
-use crate::{AnchorType, View, ViewState, ViewValue, ctx::AppCtx};
+use crate::{Anchor, Backend, ChildAnchor, Godot, View, ViewValue};
 
-pub struct App<T: View> {
-    state: T,
-    view_state: ViewState<T>,
+/// The root of a view tree, mounted as children of a node.
+pub struct App<T: View<B>, B: Backend = Godot> {
+    anchor: ChildAnchor<B>,
+    root: ViewValue<T, B>,
 }
 impl<T: View> App<T> {
+    /// Builds the view returned by `state` under `mount`.
     pub fn new<N: Inherits<Node>>(mount: Gd<N>, state: impl FnOnce() -> T) -> Self {
-        let state = state();
-        let view_state = state.build(mount.upcast(), AnchorType::ChildOf);
-        Self { state, view_state }
+        Self::attach(mount.upcast(), state)
     }
+}
+impl<T: View<B>, B: Backend> App<T, B> {
+    /// Builds the view returned by `state` under `mount`, a node of any backend.
+    pub fn attach(mount: B::Node, state: impl FnOnce() -> T) -> Self {
+        let mut anchor = ChildAnchor::new(mount);
+        let value = state();
+        let state = value.build(&mut anchor);
+        Self {
+            anchor,
+            root: ViewValue::__create(value, state),
+        }
+    }
+    /// The node the view tree is mounted under.
+    pub fn mount(&self) -> B::Node {
+        self.anchor.parent()
+    }
+    pub fn state(&self) -> &T {
+        &self.root.value
+    }
+    /// Mutates the root view and rebuilds it afterwards.
+    pub fn update<R>(&mut self, f: impl FnOnce(&mut T) -> R) -> R {
+        let out = f(&mut self.root.value);
+        self.root.__rebuild();
+        out
+    }
+    /// Tears down every node the view tree created.
     pub fn destroy(mut self) {
-        View::teardown(&mut self.view_state);
+        T::teardown(&mut self.root.state, &mut self.anchor);
+    }
+}
+
+/// Queues messages for a [`Program`]. Clones send to the same program.
+///
+/// Sending doesn't apply anything: the messages wait until the owner of the
+/// program calls [`Program::process`].
+pub struct Messenger<M> {
+    queue: Rc<RefCell<VecDeque<M>>>,
+}
+impl<M> Clone for Messenger<M> {
+    fn clone(&self) -> Self {
+        Self {
+            queue: self.queue.clone(),
+        }
+    }
+}
+impl<M: 'static> Messenger<M> {
+    /// Queues `msg`, to be applied on the next [`Program::process`].
+    pub fn send(&self, msg: M) {
+        self.queue.borrow_mut().push_back(msg);
+    }
+    /// A handler that sends a clone of `msg` each time it is called, e.g. for
+    /// [`Element::on_pressed`](crate::Element::on_pressed).
+    pub fn callback(&self, msg: M) -> impl Fn() + 'static
+    where
+        M: Clone,
+    {
+        let messenger = self.clone();
+        move || messenger.send(msg.clone())
+    }
+}
+
+/// An [`App`] whose view is a function of its state, and whose state is only
+/// changed by applying messages.
+///
+/// Views send messages through the [`Messenger`] they are given. Messages are
+/// queued and applied together by [`process`](Self::process), so handlers
+/// never run while the state is borrowed.
+///
+/// # Processing
+///
+/// Nothing calls [`process`](Self::process) for you: unlike the rebuilds of
+/// [`mutate!`](crate::mutate), the program isn't drained by the
+/// [frame hook](crate::scheduler::install_frame_hook), since it is owned by
+/// its caller. Until it is called, sent messages just wait in the queue. It is
+/// usually called from the `process` callback of the node that owns the
+/// program:
+///
+/// ```ignore
+/// fn process(&mut self, _delta: f64) {
+///     self.program.process();
+/// }
+/// ```
+pub struct Program<S, M, V: View<B>, B: Backend = Godot> {
+    state: S,
+    messenger: Messenger<M>,
+    view: ViewFn<S, M, V>,
+    update: UpdateFn<S, M>,
+    app: App<V, B>,
+}
+type ViewFn<S, M, V> = Box<dyn Fn(&S, &Messenger<M>) -> V>;
+type UpdateFn<S, M> = Box<dyn FnMut(&mut S, M)>;
+impl<S, M: 'static, V: View> Program<S, M, V> {
+    /// Builds the view of `state` under `mount`.
+    pub fn new<N: Inherits<Node>>(
+        mount: Gd<N>,
+        state: S,
+        view: impl Fn(&S, &Messenger<M>) -> V + 'static,
+        update: impl FnMut(&mut S, M) + 'static,
+    ) -> Self {
+        Self::attach(mount.upcast(), state, view, update)
+    }
+}
+impl<S, M: 'static, V: View<B>, B: Backend> Program<S, M, V, B> {
+    /// Builds the view of `state` under `mount`, a node of any backend.
+    pub fn attach(
+        mount: B::Node,
+        state: S,
+        view: impl Fn(&S, &Messenger<M>) -> V + 'static,
+        update: impl FnMut(&mut S, M) + 'static,
+    ) -> Self {
+        let messenger = Messenger {
+            queue: Rc::default(),
+        };
+        let app = App::attach(mount, || view(&state, &messenger));
+        Self {
+            state,
+            messenger,
+            view: Box::new(view),
+            update: Box::new(update),
+            app,
+        }
+    }
+    pub fn messenger(&self) -> Messenger<M> {
+        self.messenger.clone()
+    }
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+    /// Applies the messages queued so far and rebuilds the view once if there
+    /// were any. Returns whether it rebuilt.
+    ///
+    /// Messages sent while this runs are left for the next call.
+    pub fn process(&mut self) -> bool {
+        let messages = std::mem::take(&mut *self.messenger.queue.borrow_mut());
+        if messages.is_empty() {
+            return false;
+        }
+        for msg in messages {
+            (self.update)(&mut self.state, msg);
+        }
+        let view = (self.view)(&self.state, &self.messenger);
+        self.app.update(|root| *root = view);
+        true
+    }
+    /// Tears down every node the view tree created. Queued messages are dropped.
+    pub fn destroy(self) {
+        self.app.destroy();
     }
 }
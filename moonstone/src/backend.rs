@@ -0,0 +1,149 @@
+use std::rc::Rc;
+
+use godot::prelude::*;
+
+/// A handler of a signal, called with the signal's arguments.
+pub type SignalHandler<B> = Rc<dyn Fn(&[&<B as Backend>::Value])>;
+
+/// The node tree views are built into.
+///
+/// [`Godot`] is the default everywhere a backend can be chosen. Views that
+/// don't touch Godot types directly are generic over the backend, so they can
+/// also be built into an in-memory [`Mock`](crate::mock::Mock) tree.
+pub trait Backend: 'static {
+    type Node: Clone + PartialEq;
+    /// The name of a property or signal.
+    type Name: Clone + PartialEq;
+    /// The value of a property, or an argument of a signal.
+    type Value: Clone + PartialEq;
+    /// A handler connected to a signal by [`connect`](Self::connect).
+    type Connection;
+
+    /// Creates a placeholder node, used by [`AnchorStrategy::Marker`](crate::AnchorStrategy::Marker).
+    fn new_marker() -> Self::Node;
+    /// Appends `child` to the children of `parent`.
+    fn add_child(parent: &Self::Node, child: &Self::Node);
+    fn remove_child(parent: &Self::Node, child: &Self::Node);
+    /// Moves `child` to position `to` among the children of `parent`.
+    fn move_child(parent: &Self::Node, child: &Self::Node, to: usize);
+    fn parent(node: &Self::Node) -> Option<Self::Node>;
+    /// The position of `node` among its parent's children.
+    fn index(node: &Self::Node) -> usize;
+    fn child_count(node: &Self::Node) -> usize;
+    /// Frees `node` and its children once it is no longer needed.
+    fn free(node: &Self::Node);
+    fn report_error(msg: &str);
+
+    // Used by [`Element`](crate::Element).
+    fn set_prop(node: &Self::Node, name: &Self::Name, value: &Self::Value);
+    /// Calls `handler` with the arguments of `signal` whenever `node` emits it.
+    fn connect(
+        node: &Self::Node,
+        signal: &Self::Name,
+        handler: SignalHandler<Self>,
+    ) -> Self::Connection;
+    fn disconnect(node: &Self::Node, connection: &Self::Connection);
+
+    // Introspection, used by the [`snapshot`](crate::snapshot) renderer.
+    fn children(node: &Self::Node) -> Vec<Self::Node>;
+    fn class(node: &Self::Node) -> String;
+    fn name(node: &Self::Node) -> String;
+    /// Whether `node` was created by [`new_marker`](Self::new_marker).
+    fn is_marker(node: &Self::Node) -> bool;
+    /// The value of the property `prop` of `node` as text, or `None` if it has
+    /// no such property.
+    fn prop(node: &Self::Node, prop: &str) -> Option<String>;
+}
+
+/// Meta key that tags the marker nodes created by [`Godot`].
+const MARKER_META: &str = "_moonstone_marker";
+
+/// Builds views into the Godot scene tree.
+pub struct Godot;
+
+impl Backend for Godot {
+    type Node = Gd<Node>;
+    type Name = StringName;
+    type Value = Variant;
+    type Connection = (StringName, Callable);
+
+    fn new_marker() -> Self::Node {
+        let mut marker = Node::new_alloc();
+        marker.set_meta(MARKER_META, &true.to_variant());
+        marker
+    }
+
+    fn add_child(parent: &Self::Node, child: &Self::Node) {
+        parent.clone().add_child(child);
+    }
+
+    fn remove_child(parent: &Self::Node, child: &Self::Node) {
+        parent.clone().remove_child(child);
+    }
+
+    fn move_child(parent: &Self::Node, child: &Self::Node, to: usize) {
+        parent.clone().move_child(child, to as i32);
+    }
+
+    fn parent(node: &Self::Node) -> Option<Self::Node> {
+        node.get_parent()
+    }
+
+    fn index(node: &Self::Node) -> usize {
+        node.get_index() as usize
+    }
+
+    fn child_count(node: &Self::Node) -> usize {
+        node.get_child_count() as usize
+    }
+
+    fn free(node: &Self::Node) {
+        node.clone().queue_free();
+    }
+
+    fn report_error(msg: &str) {
+        godot_error!("{msg}");
+    }
+
+    fn set_prop(node: &Self::Node, name: &Self::Name, value: &Self::Value) {
+        node.clone().set(name, value);
+    }
+
+    fn connect(
+        node: &Self::Node,
+        signal: &Self::Name,
+        handler: SignalHandler<Self>,
+    ) -> Self::Connection {
+        let callable = Callable::from_fn(&signal.to_string(), move |args| handler(args));
+        node.clone().connect(signal, &callable);
+        (signal.clone(), callable)
+    }
+
+    fn disconnect(node: &Self::Node, (signal, callable): &Self::Connection) {
+        let mut node = node.clone();
+        if node.is_connected(signal, callable) {
+            node.disconnect(signal, callable);
+        }
+    }
+
+    fn children(node: &Self::Node) -> Vec<Self::Node> {
+        node.get_children().iter_shared().collect()
+    }
+
+    fn class(node: &Self::Node) -> String {
+        node.get_class().to_string()
+    }
+
+    fn name(node: &Self::Node) -> String {
+        node.get_name().to_string()
+    }
+
+    fn is_marker(node: &Self::Node) -> bool {
+        node.has_meta(MARKER_META)
+    }
+
+    fn prop(node: &Self::Node, prop: &str) -> Option<String> {
+        let value = node.get(prop);
+        (!value.is_nil()).then(|| value.to_string())
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    any::Any,
+    panic::{AssertUnwindSafe, catch_unwind},
+};
+
+use crate::{Anchor, Backend, BeforeAnchor, View};
+
+/// A view that contains panics from the `build` and `rebuild` of its child,
+/// showing a fallback view instead.
+///
+/// Built with [`error_boundary`].
+///
+/// # Cleanup after a panic
+///
+/// The nodes of the child are cleaned up on a best-effort basis:
+///
+/// - When `build` panics, the nodes the child added directly under the
+///   boundary's parent are removed and freed. Nodes it placed anywhere else,
+///   such as under the target of a [`portal`](crate::portal), are left behind.
+/// - When `rebuild` panics, the child is torn down. If the teardown panics as
+///   well, the nodes the child had before the rebuild stay where they were;
+///   only the ones added directly under the parent since are removed.
+pub struct ErrorBoundary<T, F> {
+    view: T,
+    fallback: F,
+    resets: u32,
+}
+
+/// Builds `view`, or the view returned by `fallback` for the panic message if
+/// building or rebuilding `view` panics.
+///
+/// Once the fallback is shown, `view` is only built again after a
+/// [`reset`](ErrorBoundary::reset).
+pub fn error_boundary<T, Fb, F>(view: T, fallback: F) -> ErrorBoundary<T, F>
+where
+    F: Fn(&str) -> Fb,
+{
+    ErrorBoundary {
+        view,
+        fallback,
+        resets: 0,
+    }
+}
+
+impl<T, F> ErrorBoundary<T, F> {
+    pub fn view_mut(&mut self) -> &mut T {
+        &mut self.view
+    }
+
+    /// Builds the child again on the next rebuild if the fallback is shown.
+    pub fn reset(&mut self) {
+        self.resets = self.resets.wrapping_add(1);
+    }
+
+    /// Sets the number of resets, for views that are created anew on every
+    /// rebuild. The child is built again once it differs from the last one.
+    pub fn resets(mut self, resets: u32) -> Self {
+        self.resets = resets;
+        self
+    }
+}
+
+enum Content<InnerState, FallbackState> {
+    Ok(InnerState),
+    Failed {
+        message: String,
+        fallback_state: FallbackState,
+    },
+}
+
+pub struct ErrorBoundaryState<InnerState, FallbackState, B: Backend> {
+    anchor: BeforeAnchor<B>,
+    content: Content<InnerState, FallbackState>,
+    resets: u32,
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "Box<dyn Any>".to_owned()
+    }
+}
+
+/// Removes and frees the children of the anchor's parent that are not in
+/// `before`, which were added by a view that panicked.
+fn remove_new_nodes<B: Backend>(anchor: &mut BeforeAnchor<B>, before: &[B::Node]) {
+    let parent = anchor.parent();
+    for node in B::children(&parent) {
+        if !before.contains(&node) {
+            B::remove_child(&parent, &node);
+            B::free(&node);
+        }
+    }
+    anchor.forget_entries();
+}
+
+impl<T, F> ErrorBoundary<T, F> {
+    /// Builds the child, or the fallback if that panics.
+    fn build_content<B, Fb>(&self, anchor: &mut BeforeAnchor<B>) -> Content<T::State, Fb::State>
+    where
+        B: Backend,
+        T: View<B>,
+        F: Fn(&str) -> Fb,
+        Fb: View<B>,
+    {
+        let before = B::children(&anchor.parent());
+        match catch_unwind(AssertUnwindSafe(|| self.view.build(anchor))) {
+            Ok(inner_state) => Content::Ok(inner_state),
+            Err(payload) => {
+                remove_new_nodes(anchor, &before);
+                self.fail(anchor, panic_message(&*payload))
+            }
+        }
+    }
+
+    fn fail<B, Fb>(
+        &self,
+        anchor: &mut BeforeAnchor<B>,
+        message: String,
+    ) -> Content<T::State, Fb::State>
+    where
+        B: Backend,
+        T: View<B>,
+        F: Fn(&str) -> Fb,
+        Fb: View<B>,
+    {
+        B::report_error(&format!("view panicked: {message}"));
+        Content::Failed {
+            fallback_state: (self.fallback)(&message).build(anchor),
+            message,
+        }
+    }
+}
+
+impl<B, T, F, Fb> View<B> for ErrorBoundary<T, F>
+where
+    B: Backend,
+    T: View<B>,
+    F: Fn(&str) -> Fb,
+    Fb: View<B>,
+{
+    type State = ErrorBoundaryState<T::State, Fb::State, B>;
+    type Access<'a>
+        = T::Access<'a>
+    where
+        Self: 'a;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        let mut anchor = BeforeAnchor::attach(parent_anchor);
+        let content = self.build_content(&mut anchor);
+        ErrorBoundaryState {
+            anchor,
+            content,
+            resets: self.resets,
+        }
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        match &mut state.content {
+            Content::Ok(inner_state) => {
+                let before = B::children(&state.anchor.parent());
+                let result = catch_unwind(AssertUnwindSafe(|| self.view.rebuild(inner_state)));
+                if let Err(payload) = result {
+                    // The state may be inconsistent, so its teardown may panic
+                    // as well. Whatever it added since is removed either way.
+                    let _ = catch_unwind(AssertUnwindSafe(|| {
+                        <T as View<B>>::teardown(inner_state, &mut state.anchor)
+                    }));
+                    remove_new_nodes(&mut state.anchor, &before);
+                    state.content = self.fail(&mut state.anchor, panic_message(&*payload));
+                }
+            }
+            Content::Failed { fallback_state, .. } if self.resets != state.resets => {
+                <Fb as View<B>>::teardown(fallback_state, &mut state.anchor);
+                state.content = self.build_content(&mut state.anchor);
+            }
+            Content::Failed {
+                message,
+                fallback_state,
+            } => (self.fallback)(message).rebuild(fallback_state),
+        }
+        state.resets = self.resets;
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+        match &mut state.content {
+            Content::Ok(inner_state) => <T as View<B>>::teardown(inner_state, &mut state.anchor),
+            Content::Failed { fallback_state, .. } => {
+                <Fb as View<B>>::teardown(fallback_state, &mut state.anchor)
+            }
+        }
+        state.anchor.detach(parent_anchor);
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+        match &state.content {
+            Content::Ok(inner_state) => <T as View<B>>::collect_nodes(inner_state, nodes),
+            Content::Failed { fallback_state, .. } => {
+                <Fb as View<B>>::collect_nodes(fallback_state, nodes)
+            }
+        }
+        state.anchor.collect_nodes(nodes);
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        self.view.access()
+    }
+}
@@ -0,0 +1,418 @@
+use std::{any::Any, cell::RefCell, ops::DerefMut, rc::Rc};
+
+use godot::{
+    classes::{BaseButton, Node},
+    meta::ToGodot,
+    obj::{Gd, Inherits, NewAlloc},
+    prelude::*,
+};
+
+use crate::{Anchor, Backend, ChildAnchor, Godot, SignalHandler, View, reactive::Effect};
+
+/// A node created by the view itself, with properties that follow the view.
+///
+/// Built with [`el`]. Properties are applied when the node is built, and on
+/// rebuild only the ones whose value changed are set again. A property that is
+/// dropped from the element keeps its last value.
+///
+/// Signal handlers added with [`on`](Self::on) are connected on build and
+/// disconnected on teardown. Rebuilding swaps in the new handlers without
+/// reconnecting the signals.
+pub struct Element<T: ElementClass<B>, C = (), B: Backend = Godot> {
+    props: Vec<Prop<T::Handle, B>>,
+    handlers: Vec<(B::Name, SignalHandler<B>)>,
+    bindings: Vec<(B::Name, Binding<B>)>,
+    children: C,
+}
+
+type Binding<B> = Rc<dyn Fn() -> <B as Backend>::Value>;
+
+/// The kind of node an [`Element`] creates in the backend `B`.
+#[doc(hidden)]
+pub trait ElementClass<B: Backend> {
+    /// What typed setters are called with.
+    type Handle: Clone;
+
+    fn create() -> (Self::Handle, B::Node);
+}
+
+impl<T: Inherits<Node> + NewAlloc> ElementClass<Godot> for T {
+    type Handle = Gd<T>;
+
+    fn create() -> (Self::Handle, Gd<Node>) {
+        let node = T::new_alloc();
+        (node.clone(), node.upcast())
+    }
+}
+
+/// Creates an element view for a new `T` node.
+///
+/// ```ignore
+/// el::<Button>()
+///     .prop("text", format!("Clicked {count} times"))
+///     .set(Button::set_text_direction, TextDirection::RTL)
+/// ```
+pub fn el<T: Inherits<Node> + NewAlloc>() -> Element<T> {
+    Element::new()
+}
+
+impl<T: ElementClass<B>, B: Backend> Element<T, (), B> {
+    pub(crate) fn new() -> Self {
+        Element {
+            props: vec![],
+            handlers: vec![],
+            bindings: vec![],
+            children: (),
+        }
+    }
+}
+
+impl<T: ElementClass<B>, C, B: Backend> Element<T, C, B> {
+    pub(crate) fn named_prop(mut self, name: B::Name, value: B::Value) -> Self {
+        self.props.push(Prop::Named(name, value));
+        self
+    }
+    /// Adds a prop set by calling `setter` on the node's handle.
+    ///
+    /// Setters are told apart by their type, which is unique to each function
+    /// and closure. A setter with captures, or a function pointer, can't be
+    /// told apart from others of its type, so it's called on every rebuild.
+    pub(crate) fn typed_prop<V, F>(mut self, setter: F, value: V) -> Self
+    where
+        V: PartialEq + Clone + 'static,
+        F: Fn(&mut T::Handle, V) + 'static,
+    {
+        self.props
+            .push(Prop::Typed(Rc::new(Setter { setter, value })));
+        self
+    }
+    pub(crate) fn binding(mut self, name: B::Name, value: Binding<B>) -> Self {
+        self.bindings.push((name, value));
+        self
+    }
+    /// Calls `handler` with the signal's arguments whenever `signal` is emitted.
+    pub fn on(
+        mut self,
+        signal: impl Into<B::Name>,
+        handler: impl Fn(&[&B::Value]) + 'static,
+    ) -> Self {
+        self.handlers.push((signal.into(), Rc::new(handler)));
+        self
+    }
+    /// Builds `children` as the children of this element's node.
+    pub fn children<D: View<B>>(self, children: D) -> Element<T, D, B> {
+        Element {
+            props: self.props,
+            handlers: self.handlers,
+            bindings: self.bindings,
+            children,
+        }
+    }
+}
+
+impl<T: Inherits<Node> + NewAlloc, C> Element<T, C> {
+    /// Sets the property `name` through the Godot property system.
+    pub fn prop(self, name: impl Into<StringName>, value: impl ToGodot) -> Self {
+        self.named_prop(name.into(), value.to_variant())
+    }
+    /// Sets a property through a typed setter, usually a method of the engine
+    /// class `T`.
+    ///
+    /// The setter is only called again when `value` changes.
+    pub fn set<V, F>(self, setter: F, value: V) -> Self
+    where
+        V: PartialEq + Clone + 'static,
+        F: Fn(&mut T, V) + 'static,
+        Gd<T>: DerefMut<Target = T>,
+    {
+        self.typed_prop(move |node: &mut Gd<T>, value| setter(node, value), value)
+    }
+    /// Calls `handler` whenever the button is pressed.
+    pub fn on_pressed(self, handler: impl Fn() + 'static) -> Self
+    where
+        T: Inherits<BaseButton>,
+    {
+        self.on("pressed", move |_| handler())
+    }
+    /// Keeps the property `name` set to the result of `value`, which is called
+    /// again whenever a [`Signal`](crate::reactive::Signal) it reads changes,
+    /// without rebuilding the element.
+    ///
+    /// Rebuilding the element swaps in the new closure, which follows the
+    /// signals it reads from then on, and only sets the property if it gives
+    /// another value.
+    pub fn bind<V: ToGodot>(
+        self,
+        name: impl Into<StringName>,
+        value: impl Fn() -> V + 'static,
+    ) -> Self {
+        self.binding(name.into(), Rc::new(move || value().to_variant()))
+    }
+}
+
+enum Prop<H, B: Backend> {
+    Named(B::Name, B::Value),
+    Typed(Rc<dyn TypedProp<H>>),
+}
+impl<H, B: Backend> Clone for Prop<H, B> {
+    fn clone(&self) -> Self {
+        match self {
+            Prop::Named(name, value) => Prop::Named(name.clone(), value.clone()),
+            Prop::Typed(prop) => Prop::Typed(prop.clone()),
+        }
+    }
+}
+impl<H, B: Backend> Prop<H, B> {
+    fn apply(&self, node: &B::Node, handle: &mut H) {
+        match self {
+            Prop::Named(name, value) => B::set_prop(node, name, value),
+            Prop::Typed(prop) => prop.apply(handle),
+        }
+    }
+    /// Whether applying `self` after `old` would leave the node unchanged.
+    fn same_as(&self, old: &Self) -> bool {
+        match (self, old) {
+            (Prop::Named(name, value), Prop::Named(old_name, old_value)) => {
+                name == old_name && value == old_value
+            }
+            (Prop::Typed(prop), Prop::Typed(old)) => prop.same_as(&**old),
+            _ => false,
+        }
+    }
+}
+
+/// The props of `new` that need to be applied over `old`, which are those that
+/// aren't the [`same_as`](Prop::same_as) the prop at the same index of `old`.
+fn changed_props<'a, P>(
+    new: &'a [P],
+    old: &[P],
+    same: impl Fn(&P, &P) -> bool,
+) -> impl Iterator<Item = &'a P> {
+    new.iter()
+        .enumerate()
+        .filter(move |(idx, prop)| !old.get(*idx).is_some_and(|old| same(prop, old)))
+        .map(|(_, prop)| prop)
+}
+
+/// A [`Setter`] with its value type erased.
+trait TypedProp<H> {
+    fn apply(&self, handle: &mut H);
+    fn same_as(&self, old: &dyn TypedProp<H>) -> bool;
+    fn as_any(&self) -> &dyn Any;
+}
+
+struct Setter<F, V> {
+    setter: F,
+    value: V,
+}
+impl<H, F, V> TypedProp<H> for Setter<F, V>
+where
+    F: Fn(&mut H, V) + 'static,
+    V: PartialEq + Clone + 'static,
+{
+    fn apply(&self, handle: &mut H) {
+        (self.setter)(handle, self.value.clone());
+    }
+    fn same_as(&self, old: &dyn TypedProp<H>) -> bool {
+        // Only a zero-sized setter is the one function its type names.
+        size_of::<F>() == 0
+            && old
+                .as_any()
+                .downcast_ref::<Self>()
+                .is_some_and(|old| self.value == old.value)
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A signal connection whose handler can be swapped without reconnecting.
+struct Connection<B: Backend> {
+    signal: B::Name,
+    handler: Rc<RefCell<SignalHandler<B>>>,
+    connection: B::Connection,
+}
+impl<B: Backend> Connection<B> {
+    fn connect(node: &B::Node, signal: B::Name, handler: SignalHandler<B>) -> Self {
+        let handler = Rc::new(RefCell::new(handler));
+        let connection = B::connect(node, &signal, {
+            let handler = handler.clone();
+            Rc::new(move |args| {
+                // Cloned out so the handler can trigger a rebuild that swaps it.
+                let handler = handler.borrow().clone();
+                handler(args);
+            })
+        });
+        Self {
+            signal,
+            handler,
+            connection,
+        }
+    }
+    fn disconnect(&self, node: &B::Node) {
+        B::disconnect(node, &self.connection);
+    }
+}
+
+/// A property binding that keeps the value it last set across rebuilds.
+struct Bound<B: Backend> {
+    name: B::Name,
+    /// The value the property was last set to.
+    last: Rc<RefCell<Option<B::Value>>>,
+    effect: Effect,
+}
+impl<B: Backend> Bound<B> {
+    fn bind(node: &B::Node, name: B::Name, value: Binding<B>) -> Self {
+        let last = Rc::new(RefCell::new(None));
+        let effect = Self::effect(node, &name, &last, value);
+        Self { name, last, effect }
+    }
+    /// Swaps in `value`, setting the property if it now has another value.
+    ///
+    /// The new closure may read other signals than the old one, so its effect
+    /// is tracked again from scratch.
+    fn swap(&mut self, node: &B::Node, value: &Binding<B>) {
+        self.effect = Self::effect(node, &self.name, &self.last, value.clone());
+    }
+    fn effect(
+        node: &B::Node,
+        name: &B::Name,
+        last: &Rc<RefCell<Option<B::Value>>>,
+        value: Binding<B>,
+    ) -> Effect {
+        let (node, name, last) = (node.clone(), name.clone(), last.clone());
+        Effect::new(move || set_bound::<B>(&node, &name, &last, value()))
+    }
+}
+
+fn set_bound<B: Backend>(
+    node: &B::Node,
+    name: &B::Name,
+    last: &RefCell<Option<B::Value>>,
+    value: B::Value,
+) {
+    if last.borrow().as_ref() != Some(&value) {
+        B::set_prop(node, name, &value);
+        *last.borrow_mut() = Some(value);
+    }
+}
+
+/// Reuses the leading items of `state` that match the items of `new` at the
+/// same index, passing each pair to `reuse`. The rest of `state` is passed to
+/// `remove`, and `create` makes the state of the rest of `new`.
+///
+/// Connections and bindings use it to be kept while their signals or
+/// properties stay in the same order.
+fn reuse_prefix<S, N>(
+    state: &mut Vec<S>,
+    new: &[N],
+    same: impl Fn(&S, &N) -> bool,
+    mut reuse: impl FnMut(&mut S, &N),
+    remove: impl FnMut(S),
+    create: impl FnMut(&N) -> S,
+) {
+    let kept = state
+        .iter()
+        .zip(new)
+        .take_while(|(old, new)| same(old, new))
+        .count();
+    for (old, new) in state.iter_mut().zip(new).take(kept) {
+        reuse(old, new);
+    }
+    state.drain(kept..).for_each(remove);
+    state.extend(new[kept..].iter().map(create));
+}
+
+pub struct ElementState<T: ElementClass<B>, InnerState, B: Backend = Godot> {
+    handle: T::Handle,
+    node: B::Node,
+    props: Vec<Prop<T::Handle, B>>,
+    connections: Vec<Connection<B>>,
+    bindings: Vec<Bound<B>>,
+    children_anchor: ChildAnchor<B>,
+    children: InnerState,
+}
+
+impl<T: ElementClass<B>, C: View<B>, B: Backend> View<B> for Element<T, C, B> {
+    type State = ElementState<T, C::State, B>;
+    type Access<'a>
+        = &'a C
+    where
+        Self: 'a;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        let (mut handle, node) = T::create();
+        for prop in &self.props {
+            prop.apply(&node, &mut handle);
+        }
+        let connections = self
+            .handlers
+            .iter()
+            .map(|(signal, handler)| Connection::connect(&node, signal.clone(), handler.clone()))
+            .collect();
+        let bindings = self
+            .bindings
+            .iter()
+            .map(|(name, value)| Bound::bind(&node, name.clone(), value.clone()))
+            .collect();
+        let mut children_anchor = ChildAnchor::new(node.clone());
+        let children = self.children.build(&mut children_anchor);
+        parent_anchor.add(&node);
+
+        ElementState {
+            handle,
+            node,
+            props: self.props.clone(),
+            connections,
+            bindings,
+            children_anchor,
+            children,
+        }
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        for prop in changed_props(&self.props, &state.props, Prop::same_as) {
+            prop.apply(&state.node, &mut state.handle);
+        }
+        state.props.clone_from(&self.props);
+
+        let node = &state.node;
+        reuse_prefix(
+            &mut state.connections,
+            &self.handlers,
+            |connection, (signal, _)| connection.signal == *signal,
+            |connection, (_, handler)| *connection.handler.borrow_mut() = handler.clone(),
+            |connection| connection.disconnect(node),
+            |(signal, handler)| Connection::connect(node, signal.clone(), handler.clone()),
+        );
+
+        reuse_prefix(
+            &mut state.bindings,
+            &self.bindings,
+            |bound, (name, _)| bound.name == *name,
+            |bound, (_, value)| bound.swap(node, value),
+            drop,
+            |(name, value)| Bound::bind(node, name.clone(), value.clone()),
+        );
+
+        self.children.rebuild(&mut state.children);
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+        C::teardown(&mut state.children, &mut state.children_anchor);
+        state.bindings.clear();
+        for connection in state.connections.drain(..) {
+            connection.disconnect(&state.node);
+        }
+        parent_anchor.remove(&state.node);
+        B::free(&state.node);
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+        nodes.push(state.node.clone());
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        &self.children
+    }
+}
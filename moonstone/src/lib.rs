@@ -1,7 +1,42 @@
+mod anchor;
+mod any;
+mod app;
+mod backend;
+mod boundary;
+mod element;
+mod map;
+mod memo;
+pub mod mock;
+mod one_of;
+mod portal;
+pub mod reactive;
+pub mod scheduler;
+pub mod snapshot;
+pub mod task;
 mod view;
+mod virtual_list;
 
+pub use anchor::{
+    Anchor, AnchorStrategy, Anchored, BeforeAnchor, ChildAnchor, anchor_strategy, anchored,
+    fragment, set_anchor_strategy,
+};
+pub use any::{AnyState, AnyView};
+pub use app::{App, Messenger, Program};
+pub use backend::{Backend, Godot, SignalHandler};
+pub use boundary::{ErrorBoundary, error_boundary};
+pub use element::{Element, ElementClass, el};
+pub use map::SortedMap;
+pub use memo::{Memo, memo};
 pub use moonstone_macro::viewtype;
-pub use view::{Anchor, BeforeAnchor, ChildAnchor, View, ViewValue};
+pub use one_of::{OneOf2, OneOf3, OneOf4, OneOf5, OneOf6, OneOf7, OneOf8};
+pub use portal::{Portal, portal};
+pub use view::{
+    DebugKeys, DuplicateKeyError, DuplicateKeyPolicy, Indexed, KeyedItems, View, ViewValue,
+    check_keys, duplicate_key_policy, set_duplicate_key_policy,
+};
+pub use virtual_list::{
+    ItemExtent, ScrollViewport, Viewport, VirtualList, scroll_viewport, spacer, virtual_list,
+};
 
 #[doc(hidden)]
 pub use paste as __paste;
@@ -0,0 +1,169 @@
+//! Keyed list views for maps, reconciled like a `Vec<(K, T)>`.
+//!
+//! The entries are borrowed, so no key or view is cloned into a new list.
+//! The keyed reconciliation still gathers references to them in a vector on
+//! every build and rebuild, to find duplicates and to diff them by index.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::{BuildHasher, Hash, RandomState},
+    ops::{Deref, DerefMut},
+};
+
+use crate::{
+    Anchor, Backend, View,
+    view::{
+        KeyedItems, KeyedViewState, build_keyed, collect_keyed, no_key_name, rebuild_keyed,
+        teardown_keyed,
+    },
+};
+
+impl<B: Backend, K: Ord + Hash + Clone, T: View<B>> KeyedItems<B> for BTreeMap<K, T> {
+    type Key = K;
+    type Item = T;
+    fn keyed_items(&self) -> impl Iterator<Item = (&K, &T)> {
+        self.iter()
+    }
+}
+impl<B: Backend, K: Ord + Hash + Clone, T: View<B>> View<B> for BTreeMap<K, T> {
+    type State = KeyedViewState<K, T::State, B>;
+    type Access<'a>
+        = &'a Self
+    where
+        T: 'a,
+        K: 'a;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        build_keyed(self, parent_anchor, no_key_name)
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        rebuild_keyed(self, state, no_key_name);
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+        teardown_keyed::<K, T, B>(state, parent_anchor);
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+        collect_keyed::<K, T, B>(state, nodes);
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        self
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<B: Backend, K: Hash + Eq + Clone, T: View<B>, S: BuildHasher> KeyedItems<B>
+    for indexmap::IndexMap<K, T, S>
+{
+    type Key = K;
+    type Item = T;
+    fn keyed_items(&self) -> impl Iterator<Item = (&K, &T)> {
+        self.iter()
+    }
+}
+#[cfg(feature = "indexmap")]
+impl<B: Backend, K: Hash + Eq + Clone, T: View<B>, S: BuildHasher> View<B>
+    for indexmap::IndexMap<K, T, S>
+{
+    type State = KeyedViewState<K, T::State, B>;
+    type Access<'a>
+        = &'a Self
+    where
+        T: 'a,
+        K: 'a,
+        S: 'a;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        build_keyed(self, parent_anchor, no_key_name)
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        rebuild_keyed(self, state, no_key_name);
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+        teardown_keyed::<K, T, B>(state, parent_anchor);
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+        collect_keyed::<K, T, B>(state, nodes);
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        self
+    }
+}
+
+/// A `HashMap` whose items are rendered in ascending key order.
+///
+/// `HashMap` itself isn't a view because its iteration order changes between
+/// builds, which would move nodes around on every rebuild.
+#[derive(Clone, Debug, Default)]
+pub struct SortedMap<K, T, S = RandomState>(pub HashMap<K, T, S>);
+
+impl<K, T, S> Deref for SortedMap<K, T, S> {
+    type Target = HashMap<K, T, S>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<K, T, S> DerefMut for SortedMap<K, T, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+impl<K, T, S> From<HashMap<K, T, S>> for SortedMap<K, T, S> {
+    fn from(value: HashMap<K, T, S>) -> Self {
+        Self(value)
+    }
+}
+impl<K: Ord, T, S> SortedMap<K, T, S> {
+    fn sorted(&self) -> Vec<(&K, &T)> {
+        let mut items = self.0.iter().collect::<Vec<_>>();
+        items.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        items
+    }
+}
+
+impl<B: Backend, K: Ord + Hash + Clone, T: View<B>, S: BuildHasher> KeyedItems<B>
+    for SortedMap<K, T, S>
+{
+    type Key = K;
+    type Item = T;
+    fn keyed_items(&self) -> impl Iterator<Item = (&K, &T)> {
+        self.sorted().into_iter()
+    }
+}
+impl<B: Backend, K: Ord + Hash + Clone, T: View<B>, S: BuildHasher> View<B> for SortedMap<K, T, S> {
+    type State = KeyedViewState<K, T::State, B>;
+    type Access<'a>
+        = &'a HashMap<K, T, S>
+    where
+        T: 'a,
+        K: 'a,
+        S: 'a;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        build_keyed(self.sorted(), parent_anchor, no_key_name)
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        rebuild_keyed(self.sorted(), state, no_key_name);
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+        teardown_keyed::<K, T, B>(state, parent_anchor);
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+        collect_keyed::<K, T, B>(state, nodes);
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        &self.0
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{Anchor, Backend, View};
+
+/// A view that is only constructed and rebuilt when its dependencies change.
+///
+/// Built with [`memo`].
+pub struct Memo<D, F> {
+    deps: D,
+    view: F,
+}
+
+/// Memoizes the view returned by `view`, which is only called again once `deps`
+/// no longer compares equal to the value it was last called with.
+pub fn memo<D, V, F>(deps: D, view: F) -> Memo<D, F>
+where
+    D: PartialEq + Clone,
+    F: Fn(&D) -> V,
+{
+    Memo { deps, view }
+}
+
+pub struct MemoViewState<D, InnerState> {
+    deps: D,
+    inner_state: InnerState,
+}
+impl<B, D, V, F> View<B> for Memo<D, F>
+where
+    B: Backend,
+    D: PartialEq + Clone,
+    V: View<B>,
+    F: Fn(&D) -> V,
+{
+    type State = MemoViewState<D, V::State>;
+    type Access<'a>
+        = &'a D
+    where
+        Self: 'a;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        MemoViewState {
+            deps: self.deps.clone(),
+            inner_state: (self.view)(&self.deps).build(parent_anchor),
+        }
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        if self.deps == state.deps {
+            return;
+        }
+        (self.view)(&self.deps).rebuild(&mut state.inner_state);
+        state.deps = self.deps.clone();
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+        <V as View<B>>::teardown(&mut state.inner_state, parent_anchor);
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+        <V as View<B>>::collect_nodes(&state.inner_state, nodes);
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        &self.deps
+    }
+}
@@ -0,0 +1,378 @@
+//! An in-memory node tree, so views can be built, tested and benchmarked
+//! without the Godot engine.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    fmt::Debug,
+    hash::Hash,
+    rc::{Rc, Weak},
+};
+
+use crate::{Anchor, Backend, Element, ElementClass, SignalHandler, View};
+
+/// A [`Backend`] whose nodes are [`MockNode`]s.
+pub struct Mock;
+
+struct MockNodeData {
+    class: String,
+    name: String,
+    marker: bool,
+    freed: bool,
+    props: BTreeMap<String, String>,
+    handlers: Vec<(u64, String, SignalHandler<Mock>)>,
+    parent: Weak<RefCell<MockNodeData>>,
+    children: Vec<MockNode>,
+}
+
+/// A node of the [`Mock`] tree. Clones refer to the same node.
+#[derive(Clone)]
+pub struct MockNode(Rc<RefCell<MockNodeData>>);
+
+impl PartialEq for MockNode {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+impl Eq for MockNode {}
+impl Hash for MockNode {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        Rc::as_ptr(&self.0).hash(state);
+    }
+}
+impl Debug for MockNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let data = self.0.borrow();
+        write!(f, "{}({})", data.class, data.name)
+    }
+}
+
+impl MockNode {
+    pub fn new(class: impl Into<String>, name: impl Into<String>) -> Self {
+        Self(Rc::new(RefCell::new(MockNodeData {
+            class: class.into(),
+            name: name.into(),
+            marker: false,
+            freed: false,
+            props: BTreeMap::new(),
+            handlers: vec![],
+            parent: Weak::new(),
+            children: vec![],
+        })))
+    }
+    pub fn class(&self) -> String {
+        self.0.borrow().class.clone()
+    }
+    pub fn name(&self) -> String {
+        self.0.borrow().name.clone()
+    }
+    pub fn set_name(&self, name: impl Into<String>) {
+        self.0.borrow_mut().name = name.into();
+    }
+    pub fn prop(&self, key: &str) -> Option<String> {
+        self.0.borrow().props.get(key).cloned()
+    }
+    pub fn set_prop(&self, key: impl Into<String>, value: impl Into<String>) {
+        self.0.borrow_mut().props.insert(key.into(), value.into());
+    }
+    /// Calls the handlers connected to `signal` with `args`.
+    pub fn emit(&self, signal: &str, args: &[&str]) {
+        let handlers: Vec<SignalHandler<Mock>> = (self.0.borrow().handlers.iter())
+            .filter(|(_, name, _)| name == signal)
+            .map(|(_, _, handler)| handler.clone())
+            .collect();
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let args: Vec<&String> = args.iter().collect();
+        for handler in handlers {
+            handler(&args);
+        }
+    }
+    /// How many handlers are connected to `signal`.
+    pub fn connections(&self, signal: &str) -> usize {
+        (self.0.borrow().handlers.iter())
+            .filter(|(_, name, _)| name == signal)
+            .count()
+    }
+    /// Whether this node was created by [`Backend::new_marker`].
+    pub fn is_marker(&self) -> bool {
+        self.0.borrow().marker
+    }
+    pub fn is_freed(&self) -> bool {
+        self.0.borrow().freed
+    }
+    pub fn parent(&self) -> Option<MockNode> {
+        self.0.borrow().parent.upgrade().map(MockNode)
+    }
+    pub fn children(&self) -> Vec<MockNode> {
+        self.0.borrow().children.clone()
+    }
+    /// The names of the children that aren't marker nodes, in order.
+    pub fn child_names(&self) -> Vec<String> {
+        self.0
+            .borrow()
+            .children
+            .iter()
+            .filter(|c| !c.is_marker())
+            .map(MockNode::name)
+            .collect()
+    }
+    fn position(&self, child: &MockNode) -> usize {
+        self.0
+            .borrow()
+            .children
+            .iter()
+            .position(|c| c == child)
+            .expect("node is not a child of this node")
+    }
+}
+
+/// How many tree operations the [`Mock`] backend performed on this thread.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MockStats {
+    pub added: usize,
+    pub removed: usize,
+    pub moved: usize,
+    pub freed: usize,
+    pub markers: usize,
+    pub props_set: usize,
+    pub connected: usize,
+    pub disconnected: usize,
+}
+
+thread_local! {
+    static STATS: Cell<MockStats> = Cell::new(MockStats::default());
+    static ERRORS: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+    static NEXT_CONNECTION: Cell<u64> = const { Cell::new(0) };
+}
+
+fn record(f: impl FnOnce(&mut MockStats)) {
+    let mut stats = STATS.get();
+    f(&mut stats);
+    STATS.set(stats);
+}
+
+pub fn stats() -> MockStats {
+    STATS.get()
+}
+pub fn reset_stats() {
+    STATS.set(MockStats::default());
+}
+/// Takes the errors reported through [`Backend::report_error`] so far.
+pub fn take_errors() -> Vec<String> {
+    ERRORS.take()
+}
+
+impl Backend for Mock {
+    type Node = MockNode;
+    type Name = String;
+    type Value = String;
+    type Connection = u64;
+
+    fn new_marker() -> Self::Node {
+        record(|s| s.markers += 1);
+        let node = MockNode::new("Node", "Marker");
+        node.0.borrow_mut().marker = true;
+        node
+    }
+
+    fn add_child(parent: &Self::Node, child: &Self::Node) {
+        assert!(child.parent().is_none(), "{child:?} already has a parent");
+        record(|s| s.added += 1);
+        child.0.borrow_mut().parent = Rc::downgrade(&parent.0);
+        parent.0.borrow_mut().children.push(child.clone());
+    }
+
+    fn remove_child(parent: &Self::Node, child: &Self::Node) {
+        record(|s| s.removed += 1);
+        let idx = parent.position(child);
+        parent.0.borrow_mut().children.remove(idx);
+        child.0.borrow_mut().parent = Weak::new();
+    }
+
+    fn move_child(parent: &Self::Node, child: &Self::Node, to: usize) {
+        record(|s| s.moved += 1);
+        let idx = parent.position(child);
+        let mut data = parent.0.borrow_mut();
+        let child = data.children.remove(idx);
+        data.children.insert(to, child);
+    }
+
+    fn parent(node: &Self::Node) -> Option<Self::Node> {
+        node.parent()
+    }
+
+    fn index(node: &Self::Node) -> usize {
+        node.parent().expect("node has no parent").position(node)
+    }
+
+    fn child_count(node: &Self::Node) -> usize {
+        node.0.borrow().children.len()
+    }
+
+    fn free(node: &Self::Node) {
+        record(|s| s.freed += 1);
+        if let Some(parent) = node.parent() {
+            let idx = parent.position(node);
+            parent.0.borrow_mut().children.remove(idx);
+            node.0.borrow_mut().parent = Weak::new();
+        }
+        node.0.borrow_mut().freed = true;
+    }
+
+    fn report_error(msg: &str) {
+        ERRORS.with_borrow_mut(|errors| errors.push(msg.to_owned()));
+    }
+
+    fn set_prop(node: &Self::Node, name: &Self::Name, value: &Self::Value) {
+        record(|s| s.props_set += 1);
+        if name == "name" {
+            node.set_name(value.clone());
+        } else {
+            node.set_prop(name.clone(), value.clone());
+        }
+    }
+
+    fn connect(
+        node: &Self::Node,
+        signal: &Self::Name,
+        handler: SignalHandler<Self>,
+    ) -> Self::Connection {
+        record(|s| s.connected += 1);
+        let id = NEXT_CONNECTION.get();
+        NEXT_CONNECTION.set(id + 1);
+        (node.0.borrow_mut().handlers).push((id, signal.clone(), handler));
+        id
+    }
+
+    fn disconnect(node: &Self::Node, connection: &Self::Connection) {
+        record(|s| s.disconnected += 1);
+        (node.0.borrow_mut().handlers).retain(|(id, _, _)| id != connection);
+    }
+
+    fn children(node: &Self::Node) -> Vec<Self::Node> {
+        node.children()
+    }
+
+    fn class(node: &Self::Node) -> String {
+        node.class()
+    }
+
+    fn name(node: &Self::Node) -> String {
+        node.name()
+    }
+
+    fn is_marker(node: &Self::Node) -> bool {
+        node.is_marker()
+    }
+
+    fn prop(node: &Self::Node, prop: &str) -> Option<String> {
+        node.prop(prop)
+    }
+}
+
+impl View<Mock> for MockNode {
+    type State = MockNode;
+    type Access<'a> = Self;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<Mock>) -> Self::State {
+        parent_anchor.add(self);
+        self.clone()
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        assert!(
+            self == state,
+            "a MockNode view can't be swapped for another node, use `leaf` instead"
+        );
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<Mock>) {
+        parent_anchor.remove(state);
+        Mock::free(state);
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<MockNode>) {
+        nodes.push(state.clone());
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        self.clone()
+    }
+}
+
+/// A view that creates one named node when built and renames it on rebuild.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Leaf(pub String);
+
+pub fn leaf(name: impl Into<String>) -> Leaf {
+    Leaf(name.into())
+}
+
+impl View<Mock> for Leaf {
+    type State = MockNode;
+    type Access<'a> = &'a str;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<Mock>) -> Self::State {
+        let node = MockNode::new("Leaf", self.0.clone());
+        parent_anchor.add(&node);
+        node
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        if state.name() != self.0 {
+            state.set_name(self.0.clone());
+        }
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<Mock>) {
+        parent_anchor.remove(state);
+        Mock::free(state);
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<MockNode>) {
+        nodes.push(state.clone());
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        &self.0
+    }
+}
+
+impl ElementClass<Mock> for MockNode {
+    type Handle = MockNode;
+
+    fn create() -> (MockNode, MockNode) {
+        let node = MockNode::new("Element", "element");
+        (node.clone(), node)
+    }
+}
+
+/// Creates an [`Element`] view for a new [`MockNode`].
+pub fn el() -> Element<MockNode, (), Mock> {
+    Element::new()
+}
+
+impl<C> Element<MockNode, C, Mock> {
+    /// Sets the prop `name`, or the node's name if `name` is `"name"`.
+    pub fn prop(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.named_prop(name.into(), value.into())
+    }
+    /// Sets a prop through `setter`, which is only called again when `value`
+    /// changes.
+    pub fn set<V, F>(self, setter: F, value: V) -> Self
+    where
+        V: PartialEq + Clone + 'static,
+        F: Fn(&mut MockNode, V) + 'static,
+    {
+        self.typed_prop(setter, value)
+    }
+    /// Keeps the prop `name` set to the result of `value`, like
+    /// [`Element::bind`].
+    pub fn bind<V: Into<String>>(
+        self,
+        name: impl Into<String>,
+        value: impl Fn() -> V + 'static,
+    ) -> Self {
+        self.binding(name.into(), Rc::new(move || value().into()))
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{Anchor, Backend, BeforeAnchor, Godot, View};
+
+pub struct OneOfViewState<InnerState, B: Backend = Godot> {
+    anchor: BeforeAnchor<B>,
+    inner_state: InnerState,
+}
+
+macro_rules! impl_one_of {
+    ($name:ident: $($variant:ident),*) => {
+        /// A view that is one of several view types.
+        ///
+        /// Rebuilding with the same variant rebuilds the inner view, switching
+        /// to another variant tears it down and builds the new one in its place.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum $name<$($variant),*> {
+            $($variant($variant),)*
+        }
+
+        impl<$($variant),*> $name<$($variant),*> {
+            fn build_inner<Bk: Backend>(&self, anchor: &mut dyn Anchor<Bk>) -> $name<$(<$variant as View<Bk>>::State),*>
+            where
+                $($variant: View<Bk>),*
+            {
+                match self {
+                    $($name::$variant(v) => $name::$variant(v.build(anchor)),)*
+                }
+            }
+        }
+
+        // The backend is `Bk` because the variants already take up `A` to `H`.
+        impl<Bk: Backend, $($variant: View<Bk>),*> View<Bk> for $name<$($variant),*> {
+            type State = OneOfViewState<$name<$(<$variant as View<Bk>>::State),*>, Bk>;
+            type Access<'a>
+                = &'a Self
+            where
+                Self: 'a;
+
+            fn build(&self, parent_anchor: &mut dyn Anchor<Bk>) -> Self::State {
+                let mut one_of_anchor = BeforeAnchor::attach(parent_anchor);
+
+                let inner_state = self.build_inner(&mut one_of_anchor);
+
+                OneOfViewState {
+                    anchor: one_of_anchor,
+                    inner_state,
+                }
+            }
+
+            fn rebuild(&self, state: &mut Self::State) {
+                match (self, &mut state.inner_state) {
+                    $(($name::$variant(new), $name::$variant(inner_state)) => {
+                        new.rebuild(inner_state);
+                        return;
+                    })*
+                    _ => {}
+                }
+                match &mut state.inner_state {
+                    $($name::$variant(inner_state) => {
+                        <$variant as View<Bk>>::teardown(inner_state, &mut state.anchor);
+                    })*
+                }
+                state.inner_state = self.build_inner(&mut state.anchor);
+            }
+
+            fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<Bk>) {
+                match &mut state.inner_state {
+                    $($name::$variant(inner_state) => {
+                        <$variant as View<Bk>>::teardown(inner_state, &mut state.anchor);
+                    })*
+                }
+                state.anchor.detach(parent_anchor);
+            }
+
+            fn collect_nodes(state: &Self::State, nodes: &mut Vec<Bk::Node>) {
+                match &state.inner_state {
+                    $($name::$variant(inner_state) => {
+                        <$variant as View<Bk>>::collect_nodes(inner_state, nodes);
+                    })*
+                }
+                state.anchor.collect_nodes(nodes);
+            }
+
+            fn access<'a>(&'a self) -> Self::Access<'a> {
+                self
+            }
+        }
+    };
+}
+
+impl_one_of!(OneOf2: A, B);
+impl_one_of!(OneOf3: A, B, C);
+impl_one_of!(OneOf4: A, B, C, D);
+impl_one_of!(OneOf5: A, B, C, D, E);
+impl_one_of!(OneOf6: A, B, C, D, E, F);
+impl_one_of!(OneOf7: A, B, C, D, E, F, G);
+impl_one_of!(OneOf8: A, B, C, D, E, F, G, H);
@@ -0,0 +1,67 @@
+use godot::{classes::Node, obj::Inherits, prelude::Gd};
+
+use crate::{Anchor, Backend, ChildAnchor, Godot, View};
+
+/// A view that builds its child under another node than the one its parent
+/// view builds into, such as a `CanvasLayer` for popups and overlays.
+///
+/// The child is still built, rebuilt and torn down along with the portal, but
+/// none of its nodes count as nodes of the portal's parent.
+pub struct Portal<T, B: Backend = Godot> {
+    target: B::Node,
+    view: T,
+}
+
+/// Builds `view` as the last children of `target`.
+pub fn portal<T: View, N: Inherits<Node>>(target: Gd<N>, view: T) -> Portal<T> {
+    Portal::new(target.upcast(), view)
+}
+
+impl<T: View<B>, B: Backend> Portal<T, B> {
+    /// Builds `view` as the last children of `target`, a node of any backend.
+    pub fn new(target: B::Node, view: T) -> Self {
+        Self { target, view }
+    }
+}
+
+pub struct PortalState<InnerState, B: Backend = Godot> {
+    anchor: ChildAnchor<B>,
+    inner_state: InnerState,
+}
+
+impl<B: Backend, T: View<B>> View<B> for Portal<T, B> {
+    type State = PortalState<T::State, B>;
+    type Access<'a>
+        = T::Access<'a>
+    where
+        Self: 'a;
+
+    fn build(&self, _parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        let mut anchor = ChildAnchor::new(self.target.clone());
+        let inner_state = self.view.build(&mut anchor);
+        PortalState {
+            anchor,
+            inner_state,
+        }
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        if state.anchor.parent() == self.target {
+            self.view.rebuild(&mut state.inner_state);
+            return;
+        }
+        <T as View<B>>::teardown(&mut state.inner_state, &mut state.anchor);
+        state.anchor = ChildAnchor::new(self.target.clone());
+        state.inner_state = self.view.build(&mut state.anchor);
+    }
+
+    fn teardown(state: &mut Self::State, _parent_anchor: &mut dyn Anchor<B>) {
+        <T as View<B>>::teardown(&mut state.inner_state, &mut state.anchor);
+    }
+
+    fn collect_nodes(_state: &Self::State, _nodes: &mut Vec<B::Node>) {}
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        self.view.access()
+    }
+}
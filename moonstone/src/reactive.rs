@@ -0,0 +1,431 @@
+//! Fine-grained reactivity, as an alternative to rebuilding whole fields with
+//! [`mutate!`](crate::mutate).
+//!
+//! A [`Signal`] remembers which [`Effect`]s read it, and reruns only those
+//! when it is written to. [`dynamic`] views and [`Element::bind`](crate::Element::bind)
+//! are built on effects, so writing to a signal updates just the part of the
+//! tree that depends on it.
+
+use std::{
+    cell::{OnceCell, RefCell},
+    collections::VecDeque,
+    rc::Rc,
+};
+
+use slotmap::{SlotMap, new_key_type};
+
+use crate::{
+    Anchor, Backend, View,
+    anchor::{anchor_strategy, with_strategy},
+};
+
+new_key_type! {
+    struct NodeId;
+}
+
+/// A signal or effect in the dependency graph.
+#[derive(Default)]
+struct ReactiveNode {
+    /// The effects that read this node the last time they ran.
+    subscribers: Vec<NodeId>,
+    /// The signals this effect read the last time it ran.
+    sources: Vec<NodeId>,
+    /// Set for effects.
+    run: Option<Rc<dyn Fn()>>,
+}
+
+#[derive(Default)]
+struct Runtime {
+    nodes: SlotMap<NodeId, ReactiveNode>,
+    /// The effect that is running, which signal reads subscribe.
+    observer: Option<NodeId>,
+    batch_depth: usize,
+    running: bool,
+    queue: VecDeque<NodeId>,
+}
+
+thread_local! {
+    static RUNTIME: RefCell<Runtime> = RefCell::default();
+}
+
+fn create(run: Option<Rc<dyn Fn()>>) -> NodeId {
+    RUNTIME.with_borrow_mut(|rt| {
+        rt.nodes.insert(ReactiveNode {
+            run,
+            ..Default::default()
+        })
+    })
+}
+
+fn dispose(id: NodeId) {
+    // The runtime may already be gone if this runs while the thread exits.
+    let node = RUNTIME.try_with(|rt| {
+        let mut rt = rt.borrow_mut();
+        unsubscribe(&mut rt, id);
+        let node = rt.nodes.remove(id)?;
+        for subscriber in &node.subscribers {
+            if let Some(subscriber) = rt.nodes.get_mut(*subscriber) {
+                subscriber.sources.retain(|s| *s != id);
+            }
+        }
+        Some(node)
+    });
+    // Dropped outside the borrow, since the effect may own other signals.
+    drop(node);
+}
+
+/// Removes the edges from the sources of `id` to `id`.
+fn unsubscribe(rt: &mut Runtime, id: NodeId) {
+    let Some(node) = rt.nodes.get_mut(id) else {
+        return;
+    };
+    for source in std::mem::take(&mut node.sources) {
+        if let Some(source) = rt.nodes.get_mut(source) {
+            source.subscribers.retain(|s| *s != id);
+        }
+    }
+}
+
+/// Subscribes the running effect, if any, to `id`.
+fn track(id: NodeId) {
+    RUNTIME.with_borrow_mut(|rt| {
+        let Some(observer) = rt.observer else {
+            return;
+        };
+        if !rt.nodes.contains_key(observer) {
+            return;
+        }
+        let Some(source) = rt.nodes.get_mut(id) else {
+            return;
+        };
+        if !source.subscribers.contains(&observer) {
+            source.subscribers.push(observer);
+            rt.nodes[observer].sources.push(id);
+        }
+    });
+}
+
+/// Queues the subscribers of `id` and runs them unless in a [`batch`].
+fn notify(id: NodeId) {
+    let run = RUNTIME.with_borrow_mut(|rt| {
+        let Runtime { nodes, queue, .. } = rt;
+        if let Some(node) = nodes.get(id) {
+            for subscriber in &node.subscribers {
+                if !queue.contains(subscriber) {
+                    queue.push_back(*subscriber);
+                }
+            }
+        }
+        rt.batch_depth == 0
+    });
+    if run {
+        run_queue();
+    }
+}
+
+fn run_queue() {
+    let already_running = RUNTIME.with_borrow_mut(|rt| std::mem::replace(&mut rt.running, true));
+    if already_running {
+        return;
+    }
+    struct Stop;
+    impl Drop for Stop {
+        fn drop(&mut self) {
+            RUNTIME.with_borrow_mut(|rt| rt.running = false);
+        }
+    }
+    let _stop = Stop;
+
+    // Effects queued while running are picked up by this same loop.
+    while let Some((id, run)) = RUNTIME.with_borrow_mut(|rt| {
+        let id = rt.queue.pop_front()?;
+        Some((id, rt.nodes.get(id).and_then(|node| node.run.clone())))
+    }) {
+        if let Some(run) = run {
+            run_tracked(id, || run());
+        }
+    }
+}
+
+/// Runs `f` with `id` as the observer, replacing the sources of `id` with the
+/// signals `f` reads.
+fn run_tracked<R>(id: NodeId, f: impl FnOnce() -> R) -> R {
+    let _restore = RUNTIME.with_borrow_mut(|rt| {
+        unsubscribe(rt, id);
+        RestoreObserver(rt.observer.replace(id))
+    });
+    f()
+}
+
+/// Runs `f` without subscribing the running effect to the signals it reads.
+pub fn untracked<R>(f: impl FnOnce() -> R) -> R {
+    let _restore = RUNTIME.with_borrow_mut(|rt| RestoreObserver(rt.observer.take()));
+    f()
+}
+
+struct RestoreObserver(Option<NodeId>);
+impl Drop for RestoreObserver {
+    fn drop(&mut self) {
+        RUNTIME.with_borrow_mut(|rt| rt.observer = self.0);
+    }
+}
+
+/// Runs `f`, deferring the effects of every signal written in it until it
+/// returns, so each of them runs at most once.
+pub fn batch<R>(f: impl FnOnce() -> R) -> R {
+    RUNTIME.with_borrow_mut(|rt| rt.batch_depth += 1);
+    let out = f();
+    let run = RUNTIME.with_borrow_mut(|rt| {
+        rt.batch_depth -= 1;
+        rt.batch_depth == 0
+    });
+    if run {
+        run_queue();
+    }
+    out
+}
+
+/// A value whose readers are tracked. Clones refer to the same value.
+pub struct Signal<T> {
+    inner: Rc<SignalInner<T>>,
+}
+struct SignalInner<T> {
+    id: NodeId,
+    value: RefCell<T>,
+}
+impl<T> Drop for SignalInner<T> {
+    fn drop(&mut self) {
+        dispose(self.id);
+    }
+}
+impl<T> Clone for Signal<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: 'static> Signal<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: Rc::new(SignalInner {
+                id: create(None),
+                value: RefCell::new(value),
+            }),
+        }
+    }
+    /// Reads the value, subscribing the running effect to this signal.
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        track(self.inner.id);
+        self.with_untracked(f)
+    }
+    pub fn with_untracked<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.inner.value.borrow())
+    }
+    /// Clones the value, subscribing the running effect to this signal.
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        self.with(T::clone)
+    }
+    pub fn get_untracked(&self) -> T
+    where
+        T: Clone,
+    {
+        self.with_untracked(T::clone)
+    }
+    /// Replaces the value and reruns the effects that read it.
+    pub fn set(&self, value: T) {
+        self.update(|v| *v = value);
+    }
+    /// Modifies the value in place and reruns the effects that read it.
+    pub fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let out = f(&mut self.inner.value.borrow_mut());
+        notify(self.inner.id);
+        out
+    }
+}
+
+/// Reruns a closure whenever a signal it read during its last run changes.
+///
+/// The closure runs once when the effect is created. Dropping the effect
+/// stops it.
+pub struct Effect {
+    id: NodeId,
+}
+impl Effect {
+    pub fn new(f: impl Fn() + 'static) -> Self {
+        let f = Rc::new(f);
+        let first = f.clone();
+        Self::with_first_run(move || first(), move || f())
+    }
+
+    /// Creates an effect whose first run is `first`, which unlike `rerun`
+    /// may borrow from the caller.
+    pub(crate) fn with_first_run(first: impl FnOnce(), rerun: impl Fn() + 'static) -> Self {
+        let id = create(Some(Rc::new(rerun)));
+        run_tracked(id, first);
+        Self { id }
+    }
+}
+impl Drop for Effect {
+    fn drop(&mut self) {
+        dispose(self.id);
+    }
+}
+
+/// A value derived from other signals, recomputed when they change.
+///
+/// Effects reading a memo only rerun when the recomputed value differs from
+/// the previous one.
+pub struct Memo<T> {
+    signal: Signal<T>,
+    _effect: Rc<Effect>,
+}
+impl<T> Clone for Memo<T> {
+    fn clone(&self) -> Self {
+        Self {
+            signal: self.signal.clone(),
+            _effect: self._effect.clone(),
+        }
+    }
+}
+impl<T: PartialEq + 'static> Memo<T> {
+    pub fn new(f: impl Fn() -> T + 'static) -> Self {
+        let f = Rc::new(f);
+        let signal = Rc::new(OnceCell::new());
+        let effect = Effect::with_first_run(
+            || {
+                let _ = signal.set(Signal::new(f()));
+            },
+            {
+                let f = f.clone();
+                let signal = signal.clone();
+                move || {
+                    let value = f();
+                    let signal: &Signal<T> = signal.get().unwrap();
+                    if !signal.with_untracked(|old| *old == value) {
+                        signal.set(value);
+                    }
+                }
+            },
+        );
+        Self {
+            signal: signal.get().unwrap().clone(),
+            _effect: Rc::new(effect),
+        }
+    }
+    /// Reads the value, subscribing the running effect to this memo.
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        self.signal.with(f)
+    }
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        self.signal.get()
+    }
+    pub fn get_untracked(&self) -> T
+    where
+        T: Clone,
+    {
+        self.signal.get_untracked()
+    }
+}
+
+/// A view that rebuilds itself, without its parent, whenever a signal read by
+/// its closure changes.
+///
+/// Built with [`dynamic`].
+pub struct Dynamic<F>(Rc<F>);
+
+/// Builds the view returned by `f`, and rebuilds it with a new call to `f`
+/// whenever a signal read by `f` changes.
+pub fn dynamic<V, F: Fn() -> V + 'static>(f: F) -> Dynamic<F> {
+    Dynamic(Rc::new(f))
+}
+
+pub struct DynamicState<InnerState> {
+    inner_state: Rc<RefCell<Option<InnerState>>>,
+    effect: Option<Effect>,
+}
+
+impl<F> Dynamic<F> {
+    /// An effect that rebuilds `inner_state`, after a first run of `first`.
+    fn effect<B, V>(
+        &self,
+        inner_state: &Rc<RefCell<Option<V::State>>>,
+        first: impl FnOnce(),
+    ) -> Effect
+    where
+        B: Backend,
+        V: View<B>,
+        V::State: 'static,
+        F: Fn() -> V + 'static,
+    {
+        let f = self.0.clone();
+        let inner_state = inner_state.clone();
+        let strategy = anchor_strategy();
+        Effect::with_first_run(first, move || {
+            if let Some(inner_state) = inner_state.borrow_mut().as_mut() {
+                with_strategy(strategy, || f().rebuild(inner_state));
+            }
+        })
+    }
+}
+
+impl<B, V, F> View<B> for Dynamic<F>
+where
+    B: Backend,
+    V: View<B>,
+    V::State: 'static,
+    F: Fn() -> V + 'static,
+{
+    type State = DynamicState<V::State>;
+    type Access<'a>
+        = &'a F
+    where
+        Self: 'a;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        let inner_state = Rc::new(RefCell::new(None));
+        let effect = self.effect(&inner_state, || {
+            *inner_state.borrow_mut() = Some((self.0)().build(parent_anchor));
+        });
+        DynamicState {
+            inner_state,
+            effect: Some(effect),
+        }
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        // The closure may read other signals than before, so it is tracked
+        // again from scratch.
+        state.effect = None;
+        let effect = self.effect(&state.inner_state, || {
+            if let Some(inner_state) = state.inner_state.borrow_mut().as_mut() {
+                (self.0)().rebuild(inner_state);
+            }
+        });
+        state.effect = Some(effect);
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+        state.effect = None;
+        if let Some(mut inner_state) = state.inner_state.borrow_mut().take() {
+            <V as View<B>>::teardown(&mut inner_state, parent_anchor);
+        }
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+        if let Some(inner_state) = state.inner_state.borrow().as_ref() {
+            <V as View<B>>::collect_nodes(inner_state, nodes);
+        }
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        &self.0
+    }
+}
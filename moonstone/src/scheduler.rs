@@ -0,0 +1,117 @@
+//! Batches the rebuilds requested by [`mutate!`](crate::mutate) so each
+//! view is rebuilt at most once per frame.
+//!
+//! Rebuilds are queued per object and run by [`flush`], which is called on
+//! every `process_frame` of the `SceneTree` once [`install_frame_hook`] ran,
+//! right after the [tasks](crate::task) are polled.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+};
+
+use godot::{
+    classes::{Engine, SceneTree},
+    obj::{Bounds, WithBaseField, bounds::DeclUser},
+    prelude::*,
+};
+
+struct Queue {
+    keys: HashSet<i64>,
+    pending: Vec<Box<dyn FnOnce()>>,
+}
+
+thread_local! {
+    static QUEUE: RefCell<Queue> = RefCell::new(Queue {
+        keys: HashSet::new(),
+        pending: vec![],
+    });
+    static FRAME_HOOK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Queues `rebuild` to run on the next [`flush`], unless a rebuild with the
+/// same `key` is already queued.
+pub fn schedule(key: i64, rebuild: impl FnOnce() + 'static) {
+    QUEUE.with_borrow_mut(|queue| {
+        if queue.keys.insert(key) {
+            queue.pending.push(Box::new(rebuild));
+        }
+    });
+}
+
+/// Runs every queued rebuild, in the order they were first scheduled.
+///
+/// Rebuilds scheduled while flushing are left for the next flush.
+pub fn flush() {
+    let pending = QUEUE.with_borrow_mut(|queue| {
+        queue.keys.clear();
+        std::mem::take(&mut queue.pending)
+    });
+    for rebuild in pending {
+        rebuild();
+    }
+}
+
+pub fn has_pending() -> bool {
+    QUEUE.with_borrow(|queue| !queue.pending.is_empty())
+}
+
+/// Polls the [tasks](crate::task) and then flushes the queue on every
+/// `process_frame` of the `SceneTree`.
+///
+/// Installed on the first rebuild scheduled by [`mutate!`](crate::mutate) and
+/// by [`spawn`](crate::task::spawn), so it only needs to be called directly to
+/// flush [`schedule`]d rebuilds.
+///
+/// Returns whether the hook is installed, which it can't be before the main
+/// loop is a `SceneTree`, or outside the engine altogether.
+pub fn install_frame_hook() -> bool {
+    if FRAME_HOOK.get() {
+        return true;
+    }
+    if !godot::sys::is_initialized() {
+        return false;
+    }
+    let Some(mut tree) = Engine::singleton()
+        .get_main_loop()
+        .and_then(|main_loop| main_loop.try_cast::<SceneTree>().ok())
+    else {
+        return false;
+    };
+    tree.connect(
+        "process_frame",
+        &Callable::from_fn("moonstone_flush", |_| {
+            crate::task::tick();
+            flush();
+        }),
+    );
+    FRAME_HOOK.set(true);
+    true
+}
+
+/// Implemented by `viewtype!` structs to rebuild their dirty views.
+#[doc(hidden)]
+pub trait Flush {
+    fn __flush(&mut self);
+}
+
+/// Schedules a flush of the dirty views of `obj` for the next frame.
+///
+/// Without a frame hook, such as before the `SceneTree` exists, nothing would
+/// ever flush them, so they are flushed right away instead.
+#[doc(hidden)]
+pub fn __schedule_flush<T>(obj: &mut T)
+where
+    T: WithBaseField + Flush + Bounds<Declarer = DeclUser>,
+{
+    if !install_frame_hook() {
+        obj.__flush();
+        return;
+    }
+    let mut gd = obj.to_gd();
+    schedule(gd.instance_id().to_i64(), move || {
+        if gd.is_instance_valid() {
+            gd.bind_mut().__flush();
+        }
+    });
+}
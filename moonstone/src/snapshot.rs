@@ -0,0 +1,161 @@
+//! Renders the node tree under a mount point to a stable text form and
+//! compares it against checked-in golden files.
+//!
+//! ```ignore
+//! let tree = Snapshot::new().prop("text").render::<Godot>(&app.mount());
+//! moonstone::assert_snapshot!("menu", tree);
+//! ```
+//!
+//! Snapshots live in `tests/snapshots/<name>.snap` of the calling crate. Run
+//! the tests with `MOONSTONE_UPDATE_SNAPSHOTS=1` to write the current output
+//! instead of comparing against it.
+
+use std::{fmt::Write, fs, path::Path};
+
+use crate::Backend;
+
+/// The environment variable that makes [`check_snapshot`] overwrite snapshots.
+pub const UPDATE_ENV: &str = "MOONSTONE_UPDATE_SNAPSHOTS";
+
+/// What to include when rendering a node tree.
+#[derive(Clone, Debug, Default)]
+pub struct Snapshot {
+    markers: bool,
+    props: Vec<String>,
+}
+
+impl Snapshot {
+    /// Renders class and node names only, without marker nodes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Whether to include the marker nodes of [`BeforeAnchor`](crate::BeforeAnchor)s,
+    /// rendered as `<marker>`.
+    pub fn markers(mut self, markers: bool) -> Self {
+        self.markers = markers;
+        self
+    }
+    /// Also renders the property `prop` of every node that has it.
+    pub fn prop(mut self, prop: impl Into<String>) -> Self {
+        self.props.push(prop.into());
+        self
+    }
+
+    /// Renders the children of `mount`, one node per line, indented by depth.
+    ///
+    /// Names generated by the engine (starting with `@`) change between runs,
+    /// so they are left out.
+    pub fn render<B: Backend>(&self, mount: &B::Node) -> String {
+        let mut out = String::new();
+        self.render_children::<B>(mount, 0, &mut out);
+        out
+    }
+
+    fn render_children<B: Backend>(&self, node: &B::Node, depth: usize, out: &mut String) {
+        for child in B::children(node) {
+            let indent = "  ".repeat(depth);
+            if B::is_marker(&child) {
+                if self.markers {
+                    writeln!(out, "{indent}<marker>").unwrap();
+                }
+                continue;
+            }
+
+            write!(out, "{indent}{}", B::class(&child)).unwrap();
+            let name = B::name(&child);
+            if !name.starts_with('@') {
+                write!(out, " {name:?}").unwrap();
+            }
+            for prop in &self.props {
+                if let Some(value) = B::prop(&child, prop) {
+                    write!(out, " {prop}={value:?}").unwrap();
+                }
+            }
+            out.push('\n');
+
+            self.render_children::<B>(&child, depth + 1, out);
+        }
+    }
+}
+
+/// Compares `actual` against the snapshot file at `path`, panicking with a
+/// line diff if they differ.
+///
+/// If [`UPDATE_ENV`] is set, the file is overwritten with `actual` instead.
+/// Usually called through [`assert_snapshot!`](crate::assert_snapshot).
+pub fn check_snapshot(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+    let update = std::env::var_os(UPDATE_ENV).is_some_and(|v| !v.is_empty() && v != "0");
+    if update {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(path, actual).unwrap();
+        return;
+    }
+
+    let Ok(expected) = fs::read_to_string(path) else {
+        panic!(
+            "snapshot {} does not exist, run with {UPDATE_ENV}=1 to create it",
+            path.display()
+        );
+    };
+    if expected != actual {
+        panic!(
+            "snapshot {} does not match, run with {UPDATE_ENV}=1 to update it\n{}",
+            path.display(),
+            diff(&expected, actual)
+        );
+    }
+}
+
+/// A line diff of `expected` and `actual`, with removed lines prefixed by `-`
+/// and added lines by `+`.
+fn diff(expected: &str, actual: &str) -> String {
+    let old = expected.lines().collect::<Vec<_>>();
+    let new = actual.lines().collect::<Vec<_>>();
+
+    // lcs[i][j] is the length of the longest common subsequence of old[i..]
+    // and new[j..].
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            writeln!(out, "  {}", old[i]).unwrap();
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            writeln!(out, "- {}", old[i]).unwrap();
+            i += 1;
+        } else {
+            writeln!(out, "+ {}", new[j]).unwrap();
+            j += 1;
+        }
+    }
+    out
+}
+
+/// Compares a rendered tree against `tests/snapshots/<name>.snap` in the
+/// calling crate, see [`check_snapshot`].
+#[macro_export]
+macro_rules! assert_snapshot {
+    ($name:expr, $actual:expr $(,)?) => {
+        $crate::snapshot::check_snapshot(
+            ::std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/snapshots")
+                .join(format!("{}.snap", $name)),
+            &$actual,
+        )
+    };
+}
@@ -0,0 +1,345 @@
+//! A single-threaded executor for futures that belong to the view tree.
+//!
+//! Tasks are polled by [`tick`], which the
+//! [frame hook](crate::scheduler::install_frame_hook) calls once per frame.
+//! They run on the thread that spawned them, so they can hold `Rc`s and touch
+//! nodes. Work that should happen on another thread can be awaited with
+//! [`unblock`].
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+};
+
+use parking_lot::Mutex;
+use slotmap::{SlotMap, new_key_type};
+
+use crate::{
+    Anchor, Backend, BeforeAnchor, View,
+    anchor::{anchor_strategy, with_strategy},
+};
+
+new_key_type! {
+    struct TaskId;
+}
+
+type ReadyQueue = Arc<Mutex<VecDeque<TaskId>>>;
+type LocalFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+struct Executor {
+    /// `None` while the task is being polled.
+    tasks: SlotMap<TaskId, Option<LocalFuture>>,
+    ready: ReadyQueue,
+}
+
+thread_local! {
+    static EXECUTOR: RefCell<Executor> = RefCell::new(Executor {
+        tasks: SlotMap::with_key(),
+        ready: ReadyQueue::default(),
+    });
+}
+
+struct TaskWaker {
+    id: TaskId,
+    ready: ReadyQueue,
+}
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+    fn wake_by_ref(self: &Arc<Self>) {
+        let mut ready = self.ready.lock();
+        if !ready.contains(&self.id) {
+            ready.push_back(self.id);
+        }
+    }
+}
+
+/// A spawned future. Dropping it cancels the future.
+#[must_use = "dropping a Task cancels it, use `detach` to let it run"]
+pub struct Task {
+    id: TaskId,
+}
+impl Task {
+    /// Whether the future completed or was cancelled.
+    pub fn is_finished(&self) -> bool {
+        EXECUTOR.with_borrow(|ex| !ex.tasks.contains_key(self.id))
+    }
+    /// Lets the future run to completion without keeping the handle.
+    pub fn detach(self) {
+        std::mem::forget(self);
+    }
+}
+impl Drop for Task {
+    fn drop(&mut self) {
+        // The runtime may already be gone if this runs while the thread exits.
+        let future = EXECUTOR.try_with(|ex| ex.borrow_mut().tasks.remove(self.id));
+        // Dropped outside the borrow, since the future may own other tasks.
+        drop(future);
+    }
+}
+
+/// Spawns `future` on this thread's executor. It is first polled on the next
+/// [`tick`].
+///
+/// Installs the [frame hook](crate::scheduler::install_frame_hook) if it can
+/// be. Before the `SceneTree` exists, or outside the engine, nothing ticks the
+/// executor until the hook is installed or [`tick`] is called directly.
+pub fn spawn(future: impl Future<Output = ()> + 'static) -> Task {
+    crate::scheduler::install_frame_hook();
+    EXECUTOR.with_borrow_mut(|ex| {
+        let id = ex.tasks.insert(Some(Box::pin(future)));
+        ex.ready.lock().push_back(id);
+        Task { id }
+    })
+}
+
+/// Polls every task that was woken since the last tick. Returns whether any
+/// task is still pending.
+///
+/// Tasks woken while ticking are polled on the next tick.
+pub fn tick() -> bool {
+    let (ready, queue) = EXECUTOR.with_borrow(|ex| {
+        let ready = std::mem::take(&mut *ex.ready.lock());
+        (ready, ex.ready.clone())
+    });
+    for id in ready {
+        let Some(mut future) = EXECUTOR.with_borrow_mut(|ex| ex.tasks.get_mut(id)?.take()) else {
+            continue;
+        };
+        let waker = Waker::from(Arc::new(TaskWaker {
+            id,
+            ready: queue.clone(),
+        }));
+        let poll = future.as_mut().poll(&mut Context::from_waker(&waker));
+        let finished = EXECUTOR.with_borrow_mut(|ex| match ex.tasks.get_mut(id) {
+            Some(slot) if poll.is_pending() => {
+                *slot = Some(future);
+                None
+            }
+            Some(_) => {
+                ex.tasks.remove(id);
+                Some(future)
+            }
+            // Cancelled while it was polled.
+            None => Some(future),
+        });
+        // Dropped outside the borrow, since the future may own other tasks.
+        drop(finished);
+    }
+    EXECUTOR.with_borrow(|ex| !ex.tasks.is_empty())
+}
+
+/// Runs `f` on a new thread, and resolves to its result on this one.
+pub fn unblock<T: Send + 'static>(
+    f: impl FnOnce() -> T + Send + 'static,
+) -> impl Future<Output = T> {
+    struct Shared<T> {
+        result: Option<T>,
+        waker: Option<Waker>,
+    }
+    struct Unblock<T>(Arc<Mutex<Shared<T>>>);
+    impl<T> Future for Unblock<T> {
+        type Output = T;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+            let mut shared = self.0.lock();
+            match shared.result.take() {
+                Some(result) => Poll::Ready(result),
+                None => {
+                    shared.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    let shared = Arc::new(Mutex::new(Shared {
+        result: None,
+        waker: None,
+    }));
+    std::thread::spawn({
+        let shared = shared.clone();
+        move || {
+            let result = f();
+            let mut shared = shared.lock();
+            shared.result = Some(result);
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        }
+    });
+    Unblock(shared)
+}
+
+/// A view that shows a fallback until a future resolves, then a view of its
+/// output.
+///
+/// Built with [`suspense`].
+pub struct Suspense<D, F, Fb, G> {
+    deps: D,
+    future: F,
+    fallback: Fb,
+    view: Rc<G>,
+}
+
+/// Spawns the future returned by `future` and shows `fallback` until it
+/// resolves, then the view returned by `view` for its output.
+///
+/// The future is spawned again, with the fallback shown in the meantime, once
+/// `deps` no longer compares equal to the value it was spawned with. It is
+/// cancelled when the view is torn down.
+///
+/// The future is [`spawn`]ed, so it only makes progress once the frame hook
+/// or a direct call to [`tick`] polls it.
+pub fn suspense<D, F, Fb, G>(deps: D, future: F, fallback: Fb, view: G) -> Suspense<D, F, Fb, G> {
+    Suspense {
+        deps,
+        future,
+        fallback,
+        view: Rc::new(view),
+    }
+}
+
+enum Content<FallbackState, ViewState> {
+    Fallback(FallbackState),
+    Ready(ViewState),
+}
+
+struct Shared<Out, FallbackState, ViewState, G, B: Backend> {
+    anchor: BeforeAnchor<B>,
+    content: Content<FallbackState, ViewState>,
+    output: Option<Out>,
+    /// The latest `view` closure, used once the future resolves.
+    view: Rc<G>,
+}
+
+type SharedRef<Out, FallbackState, ViewState, G, B> =
+    Rc<RefCell<Shared<Out, FallbackState, ViewState, G, B>>>;
+
+pub struct SuspenseState<D, Out, FallbackState, ViewState, G, B: Backend> {
+    deps: D,
+    shared: SharedRef<Out, FallbackState, ViewState, G, B>,
+    task: Option<Task>,
+}
+
+impl<D, F, Fb, G> Suspense<D, F, Fb, G> {
+    fn spawn<B, Fut, V>(&self, shared: &SharedRef<Fut::Output, Fb::State, V::State, G, B>) -> Task
+    where
+        B: Backend,
+        F: Fn(&D) -> Fut,
+        Fut: Future + 'static,
+        Fb: View<B>,
+        Fb::State: 'static,
+        G: Fn(&Fut::Output) -> V + 'static,
+        V: View<B>,
+        V::State: 'static,
+    {
+        let future = (self.future)(&self.deps);
+        let shared = shared.clone();
+        let strategy = anchor_strategy();
+        spawn(async move {
+            let output = future.await;
+            let shared = &mut *shared.borrow_mut();
+            if let Content::Fallback(fallback_state) = &mut shared.content {
+                <Fb as View<B>>::teardown(fallback_state, &mut shared.anchor);
+            }
+            let view = (shared.view)(&output);
+            shared.content =
+                Content::Ready(with_strategy(strategy, || view.build(&mut shared.anchor)));
+            shared.output = Some(output);
+        })
+    }
+}
+
+impl<B, D, F, Fut, Fb, G, V> View<B> for Suspense<D, F, Fb, G>
+where
+    B: Backend,
+    D: PartialEq + Clone,
+    F: Fn(&D) -> Fut,
+    Fut: Future + 'static,
+    Fb: View<B>,
+    Fb::State: 'static,
+    G: Fn(&Fut::Output) -> V + 'static,
+    V: View<B>,
+    V::State: 'static,
+{
+    type State = SuspenseState<D, Fut::Output, Fb::State, V::State, G, B>;
+    type Access<'a>
+        = &'a D
+    where
+        Self: 'a;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        let mut anchor = BeforeAnchor::attach(parent_anchor);
+        let content = Content::Fallback(self.fallback.build(&mut anchor));
+        let shared = Rc::new(RefCell::new(Shared {
+            anchor,
+            content,
+            output: None,
+            view: self.view.clone(),
+        }));
+        let task = self.spawn(&shared);
+        SuspenseState {
+            deps: self.deps.clone(),
+            shared,
+            task: Some(task),
+        }
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        let shared = &mut *state.shared.borrow_mut();
+        shared.view = self.view.clone();
+
+        if self.deps != state.deps {
+            state.task = None;
+            if let Content::Ready(view_state) = &mut shared.content {
+                <V as View<B>>::teardown(view_state, &mut shared.anchor);
+                shared.content = Content::Fallback(self.fallback.build(&mut shared.anchor));
+            }
+            shared.output = None;
+            state.deps = self.deps.clone();
+        }
+
+        match (&mut shared.content, &shared.output) {
+            (Content::Fallback(fallback_state), _) => self.fallback.rebuild(fallback_state),
+            (Content::Ready(view_state), Some(output)) => (self.view)(output).rebuild(view_state),
+            (Content::Ready(_), None) => unreachable!("ready without an output"),
+        }
+        if state.task.is_none() {
+            state.task = Some(self.spawn(&state.shared));
+        }
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+        state.task = None;
+        let shared = &mut *state.shared.borrow_mut();
+        match &mut shared.content {
+            Content::Fallback(fallback_state) => {
+                <Fb as View<B>>::teardown(fallback_state, &mut shared.anchor)
+            }
+            Content::Ready(view_state) => <V as View<B>>::teardown(view_state, &mut shared.anchor),
+        }
+        shared.anchor.detach(parent_anchor);
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+        let shared = state.shared.borrow();
+        match &shared.content {
+            Content::Fallback(fallback_state) => {
+                <Fb as View<B>>::collect_nodes(fallback_state, nodes)
+            }
+            Content::Ready(view_state) => <V as View<B>>::collect_nodes(view_state, nodes),
+        }
+        shared.anchor.collect_nodes(nodes);
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        &self.deps
+    }
+}
@@ -1,88 +1,27 @@
 use std::{
-    cell::RefCell,
-    collections::{HashMap, HashSet},
+    cell::Cell,
+    collections::HashMap,
+    error::Error,
+    fmt::{Debug, Display},
     hash::Hash,
     ops::{Deref, DerefMut},
-    rc::{Rc, Weak},
 };
 
-use godot::{classes::Button, prelude::*};
+use godot::prelude::*;
 
-pub struct ChildAnchor {
-    node: Gd<Node>,
-}
-pub struct BeforeAnchor {
-    node: Gd<Node>,
-    anchored: HashSet<Gd<Node>>,
-}
-pub trait Anchor {
-    fn new(node: Gd<Node>) -> Self
-    where
-        Self: Sized;
-    fn add(&mut self, node: &Gd<Node>);
-    fn remove(&mut self, node: &Gd<Node>);
-    fn node(&self) -> Gd<Node>;
-}
-
-impl Anchor for ChildAnchor {
-    fn add(&mut self, node: &Gd<Node>) {
-        self.node.add_child(node);
-    }
-
-    fn remove(&mut self, node: &Gd<Node>) {
-        self.node.remove_child(node);
-    }
-
-    fn new(node: Gd<Node>) -> Self
-    where
-        Self: Sized,
-    {
-        Self { node }
-    }
-
-    fn node(&self) -> Gd<Node> {
-        self.node.clone()
-    }
-}
-impl Anchor for BeforeAnchor {
-    fn add(&mut self, node: &Gd<Node>) {
-        let idx = self.node.get_index();
-        let mut parent = self.node.get_parent().unwrap();
-        parent.add_child(node);
-        parent.move_child(node, idx);
-    }
-
-    fn remove(&mut self, node: &Gd<Node>) {
-        if self.anchored.remove(node) {
-            self.node.get_parent().unwrap().remove_child(node);
-        }
-    }
-
-    fn new(node: Gd<Node>) -> Self
-    where
-        Self: Sized,
-    {
-        Self {
-            node,
-            anchored: HashSet::new(),
-        }
-    }
-
-    fn node(&self) -> Gd<Node> {
-        self.node.clone()
-    }
-}
+use crate::{Anchor, AnchorStrategy, Backend, BeforeAnchor, Godot, anchor::place};
 
-pub trait View: Sized {
+pub trait View<B: Backend = Godot>: Sized {
     type State;
     type Access<'a>
     where
         Self: 'a;
 
-    fn build(&self, parent_anchor: &mut dyn Anchor) -> Self::State;
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State;
     fn rebuild(&self, state: &mut Self::State);
-    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor);
-    fn collect_nodes(state: &Self::State, nodes: &mut Vec<Gd<Node>>);
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>);
+    /// Pushes every node this view placed under its parent anchor, in tree order.
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>);
     fn access<'a>(&'a self) -> Self::Access<'a>;
 }
 
@@ -90,14 +29,20 @@ pub trait View: Sized {
 //     fn init(&mut self);
 // }
 
-pub struct ViewValue<T: View> {
+pub struct ViewValue<T: View<B>, B: Backend = Godot> {
     pub(crate) value: T,
     pub(crate) state: T::State,
+    /// Set when `value` changed and its rebuild was deferred to a flush.
+    dirty: bool,
 }
-impl<T: View> ViewValue<T> {
+impl<T: View<B>, B: Backend> ViewValue<T, B> {
     #[doc(hidden)]
     pub fn __create(value: T, state: T::State) -> Self {
-        Self { value, state }
+        Self {
+            value,
+            state,
+            dirty: false,
+        }
     }
     #[doc(hidden)]
     pub fn __value(&self) -> &T {
@@ -110,6 +55,18 @@ impl<T: View> ViewValue<T> {
     #[doc(hidden)]
     pub fn __rebuild(&mut self) {
         self.value.rebuild(&mut self.state);
+        self.dirty = false;
+    }
+    #[doc(hidden)]
+    pub fn __mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+    /// Rebuilds if a rebuild was deferred since the last one.
+    #[doc(hidden)]
+    pub fn __flush(&mut self) {
+        if self.dirty {
+            self.__rebuild();
+        }
     }
 }
 
@@ -123,8 +80,7 @@ impl<T: Inherits<Node>> View for Gd<T> {
     type Access<'a> = Self;
 
     fn build(&self, parent_anchor: &mut dyn Anchor) -> Self::State {
-        let mut gd_anchor = BeforeAnchor::new(Node::new_alloc());
-        parent_anchor.add(&gd_anchor.node());
+        let mut gd_anchor = BeforeAnchor::attach(parent_anchor);
 
         gd_anchor.add(&self.clone().upcast());
 
@@ -147,15 +103,14 @@ impl<T: Inherits<Node>> View for Gd<T> {
 
     fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor) {
         state.anchor.remove(&state.node.clone().upcast());
-        parent_anchor.remove(&state.anchor.node());
-        state.anchor.node().queue_free();
+        state.anchor.detach(parent_anchor);
         state.node.upcast_mut().queue_free();
         // state.state.upcast_mut().queue_free();
     }
 
     fn collect_nodes(state: &Self::State, nodes: &mut Vec<Gd<Node>>) {
         nodes.push(state.node.clone().upcast());
-        nodes.push(state.anchor.node());
+        state.anchor.collect_nodes(nodes);
     }
 
     fn access<'a>(&'a self) -> Self::Access<'a> {
@@ -163,20 +118,19 @@ impl<T: Inherits<Node>> View for Gd<T> {
     }
 }
 
-pub struct OptionViewState<InnerState> {
-    anchor: BeforeAnchor,
+pub struct OptionViewState<InnerState, B: Backend = Godot> {
+    anchor: BeforeAnchor<B>,
     inner_state: Option<InnerState>,
 }
-impl<T: View> View for Option<T> {
-    type State = OptionViewState<T::State>;
+impl<B: Backend, T: View<B>> View<B> for Option<T> {
+    type State = OptionViewState<T::State, B>;
     type Access<'a>
         = &'a Self
     where
         T: 'a;
 
-    fn build(&self, parent_anchor: &mut dyn Anchor) -> Self::State {
-        let mut opt_anchor = BeforeAnchor::new(Node::new_alloc());
-        parent_anchor.add(&opt_anchor.node());
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        let mut opt_anchor = BeforeAnchor::attach(parent_anchor);
 
         let inner_state = self.as_ref().map(|v| v.build(&mut opt_anchor));
 
@@ -190,7 +144,7 @@ impl<T: View> View for Option<T> {
         match (self, state.inner_state.as_mut()) {
             (None, None) => {}
             (None, Some(inner_state)) => {
-                <T as View>::teardown(inner_state, &mut state.anchor);
+                <T as View<B>>::teardown(inner_state, &mut state.anchor);
                 state.inner_state = None;
             }
             (Some(new), None) => {
@@ -202,19 +156,18 @@ impl<T: View> View for Option<T> {
         }
     }
 
-    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor) {
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
         if let Some(is) = &mut state.inner_state {
-            <T as View>::teardown(is, &mut state.anchor);
+            <T as View<B>>::teardown(is, &mut state.anchor);
         }
-        parent_anchor.remove(&state.anchor.node());
-        state.anchor.node().clone().queue_free();
+        state.anchor.detach(parent_anchor);
     }
 
-    fn collect_nodes(state: &Self::State, nodes: &mut Vec<Gd<Node>>) {
-        nodes.push(state.anchor.node());
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
         if let Some(is) = &state.inner_state {
-            <T as View>::collect_nodes(is, nodes);
+            <T as View<B>>::collect_nodes(is, nodes);
         }
+        state.anchor.collect_nodes(nodes);
     }
 
     fn access<'a>(&'a self) -> Self::Access<'a> {
@@ -222,90 +175,550 @@ impl<T: View> View for Option<T> {
     }
 }
 
-pub struct VecViewState<K, InnerState> {
-    anchor: BeforeAnchor,
-    inner_state: Vec<(K, InnerState)>,
+pub struct ResultViewState<OkState, ErrState, B: Backend = Godot> {
+    anchor: BeforeAnchor<B>,
+    inner_state: Result<OkState, ErrState>,
 }
-impl<K: Hash + Eq + Clone, T: View> View for Vec<(K, T)> {
-    type State = VecViewState<K, T::State>;
+impl<B: Backend, T: View<B>, E: View<B>> View<B> for Result<T, E> {
+    type State = ResultViewState<T::State, E::State, B>;
     type Access<'a>
         = &'a Self
     where
         T: 'a,
-        K: 'a;
+        E: 'a;
 
-    fn build(&self, parent_anchor: &mut dyn Anchor) -> Self::State {
-        let mut vec_anchor = BeforeAnchor::new(Node::new_alloc());
-        parent_anchor.add(&vec_anchor.node());
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        let mut result_anchor = BeforeAnchor::attach(parent_anchor);
 
-        let inner_state = self
-            .iter()
-            .map(|(k, v)| (k.clone(), v.build(&mut vec_anchor)))
-            .collect::<Vec<_>>();
+        let inner_state = match self {
+            Ok(v) => Ok(v.build(&mut result_anchor)),
+            Err(e) => Err(e.build(&mut result_anchor)),
+        };
 
-        VecViewState {
-            anchor: vec_anchor,
+        ResultViewState {
+            anchor: result_anchor,
             inner_state,
         }
     }
 
     fn rebuild(&self, state: &mut Self::State) {
-        let mut total_nodes = 0;
+        match (self, &mut state.inner_state) {
+            (Ok(new), Ok(inner_state)) => new.rebuild(inner_state),
+            (Err(new), Err(inner_state)) => new.rebuild(inner_state),
+            (_, inner_state) => {
+                match inner_state {
+                    Ok(is) => <T as View<B>>::teardown(is, &mut state.anchor),
+                    Err(is) => <E as View<B>>::teardown(is, &mut state.anchor),
+                }
+                state.inner_state = match self {
+                    Ok(v) => Ok(v.build(&mut state.anchor)),
+                    Err(e) => Err(e.build(&mut state.anchor)),
+                };
+            }
+        }
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+        match &mut state.inner_state {
+            Ok(is) => <T as View<B>>::teardown(is, &mut state.anchor),
+            Err(is) => <E as View<B>>::teardown(is, &mut state.anchor),
+        }
+        state.anchor.detach(parent_anchor);
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+        match &state.inner_state {
+            Ok(is) => <T as View<B>>::collect_nodes(is, nodes),
+            Err(is) => <E as View<B>>::collect_nodes(is, nodes),
+        }
+        state.anchor.collect_nodes(nodes);
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        self
+    }
+}
+
+pub struct KeyedViewState<K, InnerState, B: Backend = Godot> {
+    anchor: BeforeAnchor<B>,
+    inner_state: Vec<KeyedItem<K, InnerState, B>>,
+    /// Set when the last build had duplicate keys and was reconciled by position.
+    positional: bool,
+}
+struct KeyedItem<K, InnerState, B: Backend> {
+    key: K,
+    /// Tracks where the item's nodes are, so the item can be moved as a whole.
+    anchor: BeforeAnchor<B>,
+    inner_state: InnerState,
+}
+impl<K, InnerState, B: Backend> KeyedItem<K, InnerState, B> {
+    fn build<T: View<B, State = InnerState>>(
+        key: K,
+        view: &T,
+        list_anchor: &mut BeforeAnchor<B>,
+    ) -> Self {
+        let mut anchor = BeforeAnchor::attach_with(list_anchor, AnchorStrategy::Positional);
+        let inner_state = view.build(&mut anchor);
+        Self {
+            key,
+            anchor,
+            inner_state,
+        }
+    }
+    fn teardown<T: View<B, State = InnerState>>(&mut self, list_anchor: &mut BeforeAnchor<B>) {
+        <T as View<B>>::teardown(&mut self.inner_state, &mut self.anchor);
+        self.anchor.detach(list_anchor);
+    }
+}
+impl<B: Backend, K: Hash + Eq + Clone, T: View<B>> View<B> for Vec<(K, T)> {
+    type State = KeyedViewState<K, T::State, B>;
+    type Access<'a>
+        = &'a Self
+    where
+        T: 'a,
+        K: 'a;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        build_keyed(self.keyed_items(), parent_anchor, no_key_name)
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        rebuild_keyed(self.keyed_items(), state, no_key_name);
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+        teardown_keyed::<K, T, B>(state, parent_anchor);
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+        collect_keyed::<K, T, B>(state, nodes);
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        self
+    }
+}
+
+/// A collection of views that is built as a keyed list, such as a
+/// `Vec<(K, T)>` or a `BTreeMap<K, T>`.
+#[doc(hidden)]
+pub trait KeyedItems<B: Backend> {
+    type Key: Hash + Eq + Clone;
+    type Item: View<B>;
+    /// The items in the order they are built in.
+    fn keyed_items(&self) -> impl Iterator<Item = (&Self::Key, &Self::Item)>;
+}
+impl<B: Backend, K: Hash + Eq + Clone, T: View<B>> KeyedItems<B> for Vec<(K, T)> {
+    type Key = K;
+    type Item = T;
+    fn keyed_items(&self) -> impl Iterator<Item = (&K, &T)> {
+        self.iter().map(|(k, v)| (k, v))
+    }
+}
+
+/// A keyed list view whose keys are named when reported as duplicates, see
+/// [`DuplicateKeyPolicy`].
+///
+/// ```ignore
+/// DebugKeys(entities.iter().map(|e| (e.id, row(e))).collect::<Vec<_>>())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DebugKeys<C>(pub C);
+
+impl<B, C> View<B> for DebugKeys<C>
+where
+    B: Backend,
+    C: KeyedItems<B>,
+    C::Key: Debug,
+{
+    type State = KeyedViewState<C::Key, <C::Item as View<B>>::State, B>;
+    type Access<'a>
+        = &'a C
+    where
+        Self: 'a;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        build_keyed(self.0.keyed_items(), parent_anchor, debug_key_name)
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        rebuild_keyed(self.0.keyed_items(), state, debug_key_name);
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+        teardown_keyed::<C::Key, C::Item, B>(state, parent_anchor);
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+        collect_keyed::<C::Key, C::Item, B>(state, nodes);
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        &self.0
+    }
+}
+
+/// What keyed list views do when the same key shows up more than once.
+///
+/// The report names the key if the view is wrapped in [`DebugKeys`], and only
+/// its type otherwise. There is no policy that returns the duplicate as an
+/// error, since building a view can't fail: use [`check_keys`] on the keys
+/// before building to handle it as one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuplicateKeyPolicy {
+    /// Panic, naming the indices of the clashing items.
+    Panic,
+    /// Report an error through the [`Backend`] and reconcile the list by
+    /// position instead of by key.
+    Fallback,
+}
+impl Default for DuplicateKeyPolicy {
+    /// [`Panic`](Self::Panic) in debug builds, [`Fallback`](Self::Fallback) in release builds.
+    fn default() -> Self {
+        if cfg!(debug_assertions) {
+            Self::Panic
+        } else {
+            Self::Fallback
+        }
+    }
+}
+
+thread_local! {
+    static DUPLICATE_KEY_POLICY: Cell<DuplicateKeyPolicy> = Cell::new(DuplicateKeyPolicy::default());
+}
+
+/// Sets how keyed list views built on this thread handle duplicate keys.
+pub fn set_duplicate_key_policy(policy: DuplicateKeyPolicy) {
+    DUPLICATE_KEY_POLICY.set(policy);
+}
+pub fn duplicate_key_policy() -> DuplicateKeyPolicy {
+    DUPLICATE_KEY_POLICY.get()
+}
+
+/// The first key found twice by [`check_keys`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateKeyError<K> {
+    pub key: K,
+    pub first: usize,
+    pub second: usize,
+}
+impl<K: Debug> Display for DuplicateKeyError<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "duplicate key {:?} at indices {} and {}",
+            self.key, self.first, self.second
+        )
+    }
+}
+impl<K: Debug> Error for DuplicateKeyError<K> {}
+
+/// Checks that `keys` are unique, for callers that want to handle duplicates
+/// themselves instead of going through the [`DuplicateKeyPolicy`].
+pub fn check_keys<'a, K: Hash + Eq + Clone + 'a>(
+    keys: impl IntoIterator<Item = &'a K>,
+) -> Result<(), DuplicateKeyError<K>> {
+    let keys = keys.into_iter().collect::<Vec<_>>();
+    match find_duplicate(keys.iter().copied()) {
+        Some((first, second)) => Err(DuplicateKeyError {
+            key: keys[second].clone(),
+            first,
+            second,
+        }),
+        None => Ok(()),
+    }
+}
+
+fn find_duplicate<'a, K: Hash + Eq + 'a>(
+    keys: impl IntoIterator<Item = &'a K>,
+) -> Option<(usize, usize)> {
+    let mut seen = HashMap::new();
+    keys.into_iter()
+        .enumerate()
+        .find_map(|(idx, k)| seen.insert(k, idx).map(|first| (first, idx)))
+}
+
+/// How a keyed list view names a key when reporting it as a duplicate, if it
+/// can.
+pub(crate) type KeyName<K> = fn(&K) -> Option<String>;
+
+pub(crate) fn no_key_name<K>(_: &K) -> Option<String> {
+    None
+}
+
+fn debug_key_name<K: Debug>(key: &K) -> Option<String> {
+    Some(format!("{key:?}"))
+}
+
+fn report_duplicate_key<K, B: Backend>(key_name: KeyName<K>, key: &K, first: usize, second: usize) {
+    let key = match key_name(key) {
+        Some(name) => format!("{name} of type"),
+        None => "of type".to_owned(),
+    };
+    let msg = format!(
+        "keyed list view has a duplicate key {key} `{}` at indices {first} and {second}",
+        std::any::type_name::<K>()
+    );
+    match duplicate_key_policy() {
+        DuplicateKeyPolicy::Panic => panic!("{msg}"),
+        DuplicateKeyPolicy::Fallback => {
+            B::report_error(&format!("{msg}, falling back to positional keys"));
+        }
+    }
+}
 
-        let mut prev_map = state
+/// Builds a keyed list. The references in `items` are gathered in a vector
+/// first, to check their keys.
+pub(crate) fn build_keyed<'a, K, T, B>(
+    items: impl IntoIterator<Item = (&'a K, &'a T)>,
+    parent_anchor: &mut dyn Anchor<B>,
+    key_name: KeyName<K>,
+) -> KeyedViewState<K, T::State, B>
+where
+    K: Hash + Eq + Clone + 'a,
+    T: View<B> + 'a,
+    B: Backend,
+{
+    let items = items.into_iter().collect::<Vec<_>>();
+    let duplicate = find_duplicate(items.iter().map(|(k, _)| *k));
+    if let Some((first, second)) = duplicate {
+        report_duplicate_key::<K, B>(key_name, items[second].0, first, second);
+    }
+
+    let mut anchor = BeforeAnchor::attach(parent_anchor);
+
+    let inner_state = items
+        .into_iter()
+        .map(|(k, v)| KeyedItem::build(k.clone(), v, &mut anchor))
+        .collect();
+
+    KeyedViewState {
+        anchor,
+        inner_state,
+        positional: duplicate.is_some(),
+    }
+}
+
+/// Reconciles a keyed list against its previous state.
+///
+/// Items are matched by key. The surviving items that form the longest run
+/// already in the right relative order stay where they are, and only the
+/// remaining ones are moved (or built) in front of their new successor.
+pub(crate) fn rebuild_keyed<'a, K, T, B>(
+    items: impl IntoIterator<Item = (&'a K, &'a T)>,
+    state: &mut KeyedViewState<K, T::State, B>,
+    key_name: KeyName<K>,
+) where
+    K: Hash + Eq + Clone + 'a,
+    T: View<B> + 'a,
+    B: Backend,
+{
+    let items = items.into_iter().collect::<Vec<_>>();
+
+    // Leading items whose keys didn't change can't have moved. The previous
+    // keys can only be trusted if they were unique.
+    let prefix = if state.positional {
+        0
+    } else {
+        state
             .inner_state
-            .drain(..)
+            .iter()
+            .zip(&items)
+            .take_while(|(old, (new, _))| old.key == **new)
+            .count()
+    };
+    if prefix == items.len() && prefix == state.inner_state.len() {
+        for (item, (_, v)) in state.inner_state.iter_mut().zip(&items) {
+            v.rebuild(&mut item.inner_state);
+        }
+        return;
+    }
+
+    let mut new_indices = HashMap::with_capacity(items.len() - prefix);
+    let mut duplicate = None;
+    for (idx, (k, _)) in items.iter().enumerate().skip(prefix) {
+        if let Some(first) = new_indices.insert(*k, idx - prefix) {
+            duplicate = Some((first + prefix, idx));
+            break;
+        }
+    }
+    if duplicate.is_none() {
+        duplicate = items[..prefix]
+            .iter()
             .enumerate()
-            .map(|(idx, is)| {
-                let mut nodes = vec![];
-                <T as View>::collect_nodes(&is.1, &mut nodes);
-                total_nodes += nodes.len();
-                (is.0, (is.1, nodes))
-            })
-            .collect::<HashMap<_, _>>();
-
-        let mut move_idx = state.anchor.node().get_index() as usize - total_nodes;
-        for (k, v) in self {
-            if let Some((k, (mut is, nodes))) = prev_map.remove_entry(k) {
-                for node in &nodes {
-                    node.get_parent().unwrap().move_child(node, move_idx as i32);
-                    move_idx += 1;
-                }
-                v.rebuild(&mut is);
-                let mut new_nodes = vec![];
-                <T as View>::collect_nodes(&is, &mut new_nodes);
-                move_idx =
-                    (move_idx as isize + new_nodes.len() as isize - nodes.len() as isize) as usize;
-                state.inner_state.push((k, is));
-            } else {
-                let is = v.build(&mut state.anchor);
-                let mut nodes = vec![];
-                <T as View>::collect_nodes(&is, &mut nodes);
-                for node in &nodes {
-                    node.get_parent().unwrap().move_child(node, move_idx as i32);
-                    move_idx += 1;
+            .find_map(|(idx, (k, _))| new_indices.get(k).map(|&other| (idx, other + prefix)));
+    }
+    if let Some((first, second)) = duplicate {
+        report_duplicate_key::<K, B>(key_name, items[second].0, first, second);
+        rebuild_positional(&items, state);
+        state.positional = true;
+        return;
+    }
+    if state.positional {
+        rebuild_positional(&items, state);
+        state.positional = false;
+        return;
+    }
+
+    for (item, (_, v)) in state.inner_state.iter_mut().zip(&items[..prefix]) {
+        v.rebuild(&mut item.inner_state);
+    }
+    let items = &items[prefix..];
+
+    let mut sources = vec![None; items.len()];
+    let mut states = items.iter().map(|_| None).collect::<Vec<_>>();
+    for (old_idx, mut item) in state.inner_state.drain(prefix..).enumerate() {
+        match new_indices.get(&item.key) {
+            Some(&new_idx) => {
+                sources[new_idx] = Some(old_idx);
+                states[new_idx] = Some(item);
+            }
+            None => item.teardown::<T>(&mut state.anchor),
+        }
+    }
+
+    let matched = sources
+        .iter()
+        .enumerate()
+        .filter_map(|(new_idx, old_idx)| old_idx.map(|old_idx| (new_idx, old_idx)))
+        .collect::<Vec<_>>();
+    let mut stable = vec![false; items.len()];
+    for i in longest_increasing_subsequence(&matched.iter().map(|v| v.1).collect::<Vec<_>>()) {
+        stable[matched[i].0] = true;
+    }
+
+    // Walking backwards means every item after the current one is already in
+    // its final place, so its first node is where the current one belongs.
+    let parent = state.anchor.parent();
+    for (idx, (k, v)) in items.iter().enumerate().rev() {
+        let (current, next) = states[idx..].split_first_mut().unwrap();
+        let item = match current {
+            Some(item) => {
+                v.rebuild(&mut item.inner_state);
+                if stable[idx] {
+                    continue;
                 }
-                state.inner_state.push((k.clone(), is));
+                item
             }
+            None => current.insert(KeyedItem::build((*k).clone(), *v, &mut state.anchor)),
+        };
+
+        let reference = next
+            .iter()
+            .flatten()
+            .find_map(|next| next.anchor.first_node())
+            .or_else(|| state.anchor.end());
+
+        let mut nodes = vec![];
+        <T as View<B>>::collect_nodes(&item.inner_state, &mut nodes);
+        for node in &nodes {
+            place::<B>(&parent, node, reference.as_ref());
         }
+        let next = next
+            .first()
+            .and_then(|next| next.as_ref().map(|next| &next.anchor));
+        state.anchor.move_nested(&item.anchor, next);
+    }
 
-        for (_, (mut inner, _)) in prev_map.drain() {
-            <T as View>::teardown(&mut inner, &mut state.anchor);
+    state.inner_state.extend(states.into_iter().flatten());
+}
+
+pub(crate) fn teardown_keyed<K, T: View<B>, B: Backend>(
+    state: &mut KeyedViewState<K, T::State, B>,
+    parent_anchor: &mut dyn Anchor<B>,
+) {
+    for item in &mut state.inner_state {
+        item.teardown::<T>(&mut state.anchor);
+    }
+    state.anchor.detach(parent_anchor);
+}
+
+pub(crate) fn collect_keyed<K, T: View<B>, B: Backend>(
+    state: &KeyedViewState<K, T::State, B>,
+    nodes: &mut Vec<B::Node>,
+) {
+    for item in &state.inner_state {
+        <T as View<B>>::collect_nodes(&item.inner_state, nodes);
+    }
+    state.anchor.collect_nodes(nodes);
+}
+
+/// Matches items to their previous state by index, ignoring keys.
+fn rebuild_positional<K, T, B>(items: &[(&K, &T)], state: &mut KeyedViewState<K, T::State, B>)
+where
+    K: Clone,
+    T: View<B>,
+    B: Backend,
+{
+    for (item, (new_k, v)) in state.inner_state.iter_mut().zip(items) {
+        item.key.clone_from(new_k);
+        v.rebuild(&mut item.inner_state);
+    }
+    if items.len() > state.inner_state.len() {
+        for (k, v) in &items[state.inner_state.len()..] {
+            let item = KeyedItem::build((*k).clone(), *v, &mut state.anchor);
+            state.inner_state.push(item);
+        }
+    } else {
+        for mut item in state.inner_state.drain(items.len()..) {
+            item.teardown::<T>(&mut state.anchor);
         }
     }
+}
 
-    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor) {
-        for (_, is) in &mut state.inner_state {
-            <T as View>::teardown(is, &mut state.anchor);
+/// Returns the indices into `seq` of one of its longest strictly increasing
+/// subsequences.
+fn longest_increasing_subsequence(seq: &[usize]) -> Vec<usize> {
+    let mut tails: Vec<usize> = vec![];
+    let mut prev = vec![None; seq.len()];
+    for (i, &v) in seq.iter().enumerate() {
+        let pos = tails.partition_point(|&t| seq[t] < v);
+        if pos > 0 {
+            prev[i] = Some(tails[pos - 1]);
+        }
+        if pos == tails.len() {
+            tails.push(i);
+        } else {
+            tails[pos] = i;
         }
-        parent_anchor.remove(&state.anchor.node());
-        state.anchor.node().queue_free();
     }
 
-    fn collect_nodes(state: &Self::State, nodes: &mut Vec<Gd<Node>>) {
-        nodes.push(state.anchor.node());
-        for (_, is) in &state.inner_state {
-            <T as View>::collect_nodes(is, nodes);
+    let mut out = vec![];
+    let mut cur = tails.last().copied();
+    while let Some(i) = cur {
+        out.push(i);
+        cur = prev[i];
+    }
+    out.reverse();
+    out
+}
+
+impl<B: Backend, T: View<B>, const N: usize> View<B> for [T; N] {
+    type State = [T::State; N];
+    type Access<'a>
+        = &'a Self
+    where
+        T: 'a;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        std::array::from_fn(|i| self[i].build(parent_anchor))
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        for (v, is) in self.iter().zip(state) {
+            v.rebuild(is);
+        }
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+        for is in state {
+            <T as View<B>>::teardown(is, parent_anchor);
+        }
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+        for is in state {
+            <T as View<B>>::collect_nodes(is, nodes);
         }
     }
 
@@ -314,9 +727,153 @@ impl<K: Hash + Eq + Clone, T: View> View for Vec<(K, T)> {
     }
 }
 
+/// A list of views keyed by their position.
+///
+/// Unlike `Vec<(K, T)>`, items are never moved: rebuilding matches items by
+/// index and only builds or tears down the tail when the length changes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Indexed<T>(pub Vec<T>);
+
+impl<T> Deref for Indexed<T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<T> DerefMut for Indexed<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+impl<T> From<Vec<T>> for Indexed<T> {
+    fn from(value: Vec<T>) -> Self {
+        Self(value)
+    }
+}
+impl<T> FromIterator<T> for Indexed<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+pub struct IndexedViewState<InnerState, B: Backend = Godot> {
+    anchor: BeforeAnchor<B>,
+    inner_state: Vec<InnerState>,
+}
+impl<B: Backend, T: View<B>> View<B> for Indexed<T> {
+    type State = IndexedViewState<T::State, B>;
+    type Access<'a>
+        = &'a [T]
+    where
+        T: 'a;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        let mut list_anchor = BeforeAnchor::attach(parent_anchor);
+
+        let inner_state = self.iter().map(|v| v.build(&mut list_anchor)).collect();
+
+        IndexedViewState {
+            anchor: list_anchor,
+            inner_state,
+        }
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        for (v, is) in self.iter().zip(&mut state.inner_state) {
+            v.rebuild(is);
+        }
+        if self.len() > state.inner_state.len() {
+            for v in &self[state.inner_state.len()..] {
+                state.inner_state.push(v.build(&mut state.anchor));
+            }
+        } else {
+            for mut is in state.inner_state.drain(self.len()..) {
+                <T as View<B>>::teardown(&mut is, &mut state.anchor);
+            }
+        }
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+        for is in &mut state.inner_state {
+            <T as View<B>>::teardown(is, &mut state.anchor);
+        }
+        state.anchor.detach(parent_anchor);
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+        for is in &state.inner_state {
+            <T as View<B>>::collect_nodes(is, nodes);
+        }
+        state.anchor.collect_nodes(nodes);
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        &self.0
+    }
+}
+
+macro_rules! impl_view_tuple {
+    ($($name:ident $idx:tt),*) => {
+        #[allow(unused_variables, clippy::unused_unit)]
+        impl<B: Backend, $($name: View<B>),*> View<B> for ($($name,)*) {
+            type State = ($($name::State,)*);
+            type Access<'a>
+                = &'a Self
+            where
+                Self: 'a;
+
+            fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+                ($(self.$idx.build(parent_anchor),)*)
+            }
+
+            fn rebuild(&self, state: &mut Self::State) {
+                $(self.$idx.rebuild(&mut state.$idx);)*
+            }
+
+            fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+                $(<$name as View<B>>::teardown(&mut state.$idx, parent_anchor);)*
+            }
+
+            fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+                $(<$name as View<B>>::collect_nodes(&state.$idx, nodes);)*
+            }
+
+            fn access<'a>(&'a self) -> Self::Access<'a> {
+                self
+            }
+        }
+    };
+}
+
+impl_view_tuple!();
+impl_view_tuple!(T0 0);
+impl_view_tuple!(T0 0, T1 1);
+impl_view_tuple!(T0 0, T1 1, T2 2);
+impl_view_tuple!(T0 0, T1 1, T2 2, T3 3);
+impl_view_tuple!(T0 0, T1 1, T2 2, T3 3, T4 4);
+impl_view_tuple!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5);
+impl_view_tuple!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6);
+impl_view_tuple!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7);
+impl_view_tuple!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8);
+impl_view_tuple!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9);
+impl_view_tuple!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10);
+impl_view_tuple!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11);
+
+/// Mutates the listed view fields of a `viewtype!` struct in `body`.
+///
+/// The fields are marked dirty and rebuilt together with every other pending
+/// rebuild on the next frame, see [`scheduler`](crate::scheduler), or right
+/// away if there is no `SceneTree` to hook into yet. Prefix the object with
+/// `sync` to rebuild them at the end of `body` instead.
+///
+/// ```ignore
+/// mutate!(self{items}, { items.push((id, label)) });
+/// mutate!(sync self{items}, { items.clear() });
+/// ```
 #[macro_export]
 macro_rules! mutate {
-    ($obj:ident{$($field:ident),* $(,)?}, {
+    (sync $obj:ident{$($field:ident),* $(,)?}, {
         $($body:tt)*
     }) => {
         {
@@ -336,4 +893,25 @@ macro_rules! mutate {
             out
         }
     };
+    ($obj:ident{$($field:ident),* $(,)?}, {
+        $($body:tt)*
+    }) => {
+        {
+            $crate::__paste::paste! {
+                $(
+                    let $field = $obj.[< __DONT_USE_THIS_DIRECTLY_ $field >].__value_mut();
+                )*
+            }
+            let out = {
+                $($body)*
+            };
+            $crate::__paste::paste! {
+                $(
+                    $obj.[< __DONT_USE_THIS_DIRECTLY_ $field >].__mark_dirty();
+                )*
+            }
+            $crate::scheduler::__schedule_flush(&mut *$obj);
+            out
+        }
+    };
 }
@@ -0,0 +1,437 @@
+use std::{cell::RefCell, hash::Hash, ops::Range, rc::Rc};
+
+use godot::{
+    classes::{Control, ScrollContainer},
+    prelude::*,
+};
+
+use crate::{
+    Anchor, Backend, BeforeAnchor, Element, View,
+    anchor::{anchor_strategy, with_strategy},
+    el,
+    reactive::{Effect, Signal, untracked},
+    view::{
+        KeyedViewState, build_keyed, collect_keyed, no_key_name, rebuild_keyed, teardown_keyed,
+    },
+};
+
+/// The part of a [`VirtualList`] that is visible, along the axis it scrolls.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Viewport {
+    /// How far the list is scrolled.
+    pub offset: f32,
+    /// How much of the list fits on screen.
+    pub extent: f32,
+}
+
+/// The extent of each item of a [`VirtualList`] along the axis it scrolls.
+#[derive(Clone)]
+pub enum ItemExtent {
+    /// Every item is this big.
+    Fixed(f32),
+    /// The extent of the item at an index. Called for every item whenever the
+    /// list is rebuilt.
+    Measure(Rc<dyn Fn(usize) -> f32>),
+}
+impl ItemExtent {
+    pub fn measure(f: impl Fn(usize) -> f32 + 'static) -> Self {
+        Self::Measure(Rc::new(f))
+    }
+}
+
+/// Where each item starts, worked out from an [`ItemExtent`].
+enum Layout {
+    Fixed(f32),
+    /// The start of every item, followed by the end of the last one.
+    Measured(Vec<f32>),
+}
+impl Layout {
+    fn new(extent: &ItemExtent, len: usize) -> Self {
+        match extent {
+            ItemExtent::Fixed(extent) => Self::Fixed(*extent),
+            ItemExtent::Measure(measure) => {
+                let mut starts = Vec::with_capacity(len + 1);
+                let mut start = 0.0;
+                starts.push(start);
+                for idx in 0..len {
+                    start += measure(idx);
+                    starts.push(start);
+                }
+                Self::Measured(starts)
+            }
+        }
+    }
+
+    fn start(&self, idx: usize) -> f32 {
+        match self {
+            Self::Fixed(extent) => idx as f32 * extent,
+            Self::Measured(starts) => starts[idx],
+        }
+    }
+
+    /// The items that overlap `viewport`.
+    fn visible(&self, viewport: Viewport, len: usize) -> Range<usize> {
+        let end = viewport.offset + viewport.extent;
+        match self {
+            Self::Fixed(extent) if *extent <= 0.0 => 0..len,
+            Self::Fixed(extent) => {
+                let first = (viewport.offset / extent).floor().max(0.0) as usize;
+                let last = (end / extent).ceil().max(0.0) as usize;
+                first.min(len)..last.min(len)
+            }
+            Self::Measured(starts) => {
+                let first = starts[1..].partition_point(|&item_end| item_end <= viewport.offset);
+                let last = starts[..len].partition_point(|&start| start < end);
+                first.min(last)..last
+            }
+        }
+    }
+}
+
+/// A list view that only builds the items in its [`Viewport`], plus a few
+/// on either side.
+///
+/// The items are reconciled by key like a `Vec<(K, V)>`, so those that stay
+/// visible while scrolling keep their state. The space taken by the items that
+/// aren't built is filled by two spacer views, one before and one after the
+/// built items.
+///
+/// Scrolling only rebuilds the list itself, through an effect that follows
+/// the viewport signal.
+///
+/// Item states aren't recycled: an item that scrolls out of range is torn
+/// down, and one that scrolls in is built anew. Handing the state of one key
+/// to another would break the keyed semantics, so state that should outlive
+/// scrolling belongs outside the item views.
+pub struct VirtualList<F, S> {
+    len: usize,
+    extent: ItemExtent,
+    overscan: usize,
+    viewport: Signal<Viewport>,
+    item: Rc<F>,
+    spacer: Rc<S>,
+}
+
+impl<F, S> VirtualList<F, S> {
+    /// A list of `len` items, where `item` returns the key and view of the
+    /// item at an index and `spacer` a view that takes up the given extent.
+    pub fn new(
+        len: usize,
+        extent: ItemExtent,
+        viewport: Signal<Viewport>,
+        spacer: S,
+        item: F,
+    ) -> Self {
+        Self {
+            len,
+            extent,
+            overscan: 2,
+            viewport,
+            item: Rc::new(item),
+            spacer: Rc::new(spacer),
+        }
+    }
+
+    /// Sets how many items are built on each side of the viewport, 2 by
+    /// default.
+    pub fn overscan(mut self, overscan: usize) -> Self {
+        self.overscan = overscan;
+        self
+    }
+}
+
+/// A [`VirtualList`] of `len` items in a `ScrollContainer`, whose viewport is
+/// tracked by a [`scroll_viewport`].
+///
+/// The list is assumed to start at the top of the scrolled content.
+pub fn virtual_list<K, V, F>(
+    len: usize,
+    extent: ItemExtent,
+    viewport: Signal<Viewport>,
+    item: F,
+) -> VirtualList<F, fn(f32) -> Element<Control>>
+where
+    F: Fn(usize) -> (K, V),
+{
+    VirtualList::new(len, extent, viewport, spacer, item)
+}
+
+/// A `Control` that takes up `extent` vertically.
+pub fn spacer(extent: f32) -> Element<Control> {
+    el::<Control>().set(Control::set_custom_minimum_size, Vector2::new(0.0, extent))
+}
+
+/// Tracks the vertical scroll position and size of a `ScrollContainer` in a
+/// [`Viewport`] signal.
+///
+/// Made by [`scroll_viewport`]. The signal stops following the container when
+/// this is dropped.
+#[must_use = "dropping a ScrollViewport stops tracking the scroll position"]
+pub struct ScrollViewport {
+    viewport: Signal<Viewport>,
+    scroll: Gd<ScrollContainer>,
+    callable: Callable,
+}
+impl ScrollViewport {
+    pub fn signal(&self) -> Signal<Viewport> {
+        self.viewport.clone()
+    }
+}
+impl Drop for ScrollViewport {
+    fn drop(&mut self) {
+        if !self.scroll.is_instance_valid() {
+            return;
+        }
+        if let Some(mut bar) = self.scroll.get_v_scroll_bar()
+            && bar.is_connected("value_changed", &self.callable)
+        {
+            bar.disconnect("value_changed", &self.callable);
+        }
+        if self.scroll.is_connected("resized", &self.callable) {
+            self.scroll.disconnect("resized", &self.callable);
+        }
+    }
+}
+
+/// Starts tracking the vertical scroll position and size of `scroll`.
+///
+/// The returned guard must be kept alive for as long as the list is shown,
+/// usually next to the list's owner.
+pub fn scroll_viewport(scroll: &Gd<ScrollContainer>) -> ScrollViewport {
+    fn read(scroll: &Gd<ScrollContainer>) -> Viewport {
+        Viewport {
+            offset: scroll.get_v_scroll() as f32,
+            extent: scroll.get_size().y,
+        }
+    }
+
+    let viewport = Signal::new(read(scroll));
+    let callable = Callable::from_fn("moonstone_scroll_viewport", {
+        let viewport = viewport.clone();
+        let scroll = scroll.clone();
+        move |_| {
+            if scroll.is_instance_valid() {
+                viewport.set(read(&scroll));
+            }
+        }
+    });
+    let mut scroll = scroll.clone();
+    if let Some(mut bar) = scroll.get_v_scroll_bar() {
+        bar.connect("value_changed", &callable);
+    }
+    scroll.connect("resized", &callable);
+    ScrollViewport {
+        viewport,
+        scroll,
+        callable,
+    }
+}
+
+struct Rendered<K, InnerState, SpacerState, B: Backend> {
+    anchor: BeforeAnchor<B>,
+    before: SpacerState,
+    items: KeyedViewState<K, InnerState, B>,
+    after: SpacerState,
+    range: Range<usize>,
+}
+
+type RenderedRef<K, InnerState, SpacerState, B> =
+    Rc<RefCell<Option<Rendered<K, InnerState, SpacerState, B>>>>;
+
+pub struct VirtualListState<K, InnerState, SpacerState, B: Backend> {
+    rendered: RenderedRef<K, InnerState, SpacerState, B>,
+    effect: Option<Effect>,
+}
+
+/// What an effect of a [`VirtualList`] needs to render its items.
+struct Renderer<F, S> {
+    len: usize,
+    layout: Layout,
+    overscan: usize,
+    viewport: Signal<Viewport>,
+    item: Rc<F>,
+    spacer: Rc<S>,
+}
+
+impl<F, S> Renderer<F, S> {
+    /// The items to build, tracking the viewport.
+    fn range(&self) -> Range<usize> {
+        let visible = self.layout.visible(self.viewport.get(), self.len);
+        visible.start.saturating_sub(self.overscan)..(visible.end + self.overscan).min(self.len)
+    }
+
+    fn items<K, V>(&self, range: Range<usize>) -> Vec<(K, V)>
+    where
+        F: Fn(usize) -> (K, V),
+    {
+        range.map(|idx| (self.item)(idx)).collect()
+    }
+
+    fn spacers(&self, range: &Range<usize>) -> (f32, f32) {
+        let end = self.layout.start(self.len);
+        (
+            self.layout.start(range.start),
+            end - self.layout.start(range.end),
+        )
+    }
+
+    /// Rebuilds `rendered` for the current viewport. Unless `force` is set,
+    /// nothing happens if the same items are visible as before.
+    fn render<B, K, V, Sv>(&self, rendered: &mut Rendered<K, V::State, Sv::State, B>, force: bool)
+    where
+        B: Backend,
+        K: Hash + Eq + Clone,
+        V: View<B>,
+        Sv: View<B>,
+        F: Fn(usize) -> (K, V),
+        S: Fn(f32) -> Sv,
+    {
+        let range = self.range();
+        if !force && range == rendered.range {
+            return;
+        }
+        untracked(|| {
+            let items = self.items(range.clone());
+            rebuild_keyed(
+                items.iter().map(|(k, v)| (k, v)),
+                &mut rendered.items,
+                no_key_name,
+            );
+            let (before, after) = self.spacers(&range);
+            (self.spacer)(before).rebuild(&mut rendered.before);
+            (self.spacer)(after).rebuild(&mut rendered.after);
+        });
+        rendered.range = range;
+    }
+}
+
+impl<F, S> VirtualList<F, S> {
+    fn renderer(&self) -> Renderer<F, S> {
+        Renderer {
+            len: self.len,
+            layout: Layout::new(&self.extent, self.len),
+            overscan: self.overscan,
+            viewport: self.viewport.clone(),
+            item: self.item.clone(),
+            spacer: self.spacer.clone(),
+        }
+    }
+}
+
+impl<B, K, V, F, Sv, S> View<B> for VirtualList<F, S>
+where
+    B: Backend,
+    K: Hash + Eq + Clone + 'static,
+    V: View<B>,
+    V::State: 'static,
+    F: Fn(usize) -> (K, V) + 'static,
+    Sv: View<B>,
+    Sv::State: 'static,
+    S: Fn(f32) -> Sv + 'static,
+{
+    type State = VirtualListState<K, V::State, Sv::State, B>;
+    type Access<'a>
+        = &'a Self
+    where
+        Self: 'a;
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<B>) -> Self::State {
+        let renderer = Rc::new(self.renderer());
+        let rendered = Rc::new(RefCell::new(None));
+        let first = {
+            let renderer = renderer.clone();
+            let rendered = rendered.clone();
+            let mut anchor = BeforeAnchor::attach(parent_anchor);
+            move || {
+                let range = renderer.range();
+                let state = untracked(|| {
+                    let (before, after) = renderer.spacers(&range);
+                    let before = (renderer.spacer)(before).build(&mut anchor);
+                    let items = renderer.items(range.clone());
+                    let items =
+                        build_keyed(items.iter().map(|(k, v)| (k, v)), &mut anchor, no_key_name);
+                    let after = (renderer.spacer)(after).build(&mut anchor);
+                    Rendered {
+                        anchor,
+                        before,
+                        items,
+                        after,
+                        range,
+                    }
+                });
+                *rendered.borrow_mut() = Some(state);
+            }
+        };
+        let effect = effect(renderer, &rendered, first);
+        VirtualListState {
+            rendered,
+            effect: Some(effect),
+        }
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        // The items may have changed along with the closures, so they are
+        // rebuilt even if the same ones are visible.
+        state.effect = None;
+        let renderer = Rc::new(self.renderer());
+        let first = {
+            let renderer = renderer.clone();
+            let rendered = state.rendered.clone();
+            move || {
+                if let Some(rendered) = rendered.borrow_mut().as_mut() {
+                    renderer.render(rendered, true);
+                }
+            }
+        };
+        state.effect = Some(effect(renderer, &state.rendered, first));
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<B>) {
+        state.effect = None;
+        if let Some(mut rendered) = state.rendered.borrow_mut().take() {
+            <Sv as View<B>>::teardown(&mut rendered.before, &mut rendered.anchor);
+            teardown_keyed::<K, V, B>(&mut rendered.items, &mut rendered.anchor);
+            <Sv as View<B>>::teardown(&mut rendered.after, &mut rendered.anchor);
+            rendered.anchor.detach(parent_anchor);
+        }
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<B::Node>) {
+        if let Some(rendered) = state.rendered.borrow().as_ref() {
+            <Sv as View<B>>::collect_nodes(&rendered.before, nodes);
+            collect_keyed::<K, V, B>(&rendered.items, nodes);
+            <Sv as View<B>>::collect_nodes(&rendered.after, nodes);
+            rendered.anchor.collect_nodes(nodes);
+        }
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {
+        self
+    }
+}
+
+/// An effect that renders `rendered` again whenever the viewport changes,
+/// after a first run of `first`.
+fn effect<B, K, V, F, Sv, S>(
+    renderer: Rc<Renderer<F, S>>,
+    rendered: &RenderedRef<K, V::State, Sv::State, B>,
+    first: impl FnOnce(),
+) -> Effect
+where
+    B: Backend,
+    K: Hash + Eq + Clone + 'static,
+    V: View<B>,
+    V::State: 'static,
+    F: Fn(usize) -> (K, V) + 'static,
+    Sv: View<B>,
+    Sv::State: 'static,
+    S: Fn(f32) -> Sv + 'static,
+{
+    let rendered = rendered.clone();
+    let strategy = anchor_strategy();
+    Effect::with_first_run(first, move || {
+        if let Some(rendered) = rendered.borrow_mut().as_mut() {
+            with_strategy(strategy, || renderer.render(rendered, false));
+        }
+    })
+}
@@ -0,0 +1,103 @@
+use moonstone::{
+    Anchor, App, Backend, View, error_boundary,
+    mock::{Mock, MockNode, leaf, take_errors},
+};
+
+/// A leaf that panics while being built or rebuilt if told to.
+struct Fragile {
+    name: &'static str,
+    panics: bool,
+}
+
+fn fragile(name: &'static str, panics: bool) -> Fragile {
+    Fragile { name, panics }
+}
+
+impl View<Mock> for Fragile {
+    type State = MockNode;
+    type Access<'a> = ();
+
+    fn build(&self, parent_anchor: &mut dyn Anchor<Mock>) -> Self::State {
+        let node = MockNode::new("Leaf", self.name);
+        parent_anchor.add(&node);
+        if self.panics {
+            panic!("{} broke", self.name);
+        }
+        node
+    }
+
+    fn rebuild(&self, state: &mut Self::State) {
+        if self.panics {
+            panic!("{} broke", self.name);
+        }
+        state.set_name(self.name);
+    }
+
+    fn teardown(state: &mut Self::State, parent_anchor: &mut dyn Anchor<Mock>) {
+        parent_anchor.remove(state);
+        Mock::free(state);
+    }
+
+    fn collect_nodes(state: &Self::State, nodes: &mut Vec<MockNode>) {
+        nodes.push(state.clone());
+    }
+
+    fn access<'a>(&'a self) -> Self::Access<'a> {}
+}
+
+#[test]
+fn panics_while_building_show_the_fallback() {
+    let root = MockNode::new("Root", "root");
+    let app = App::<_, Mock>::attach(root.clone(), || {
+        (
+            error_boundary((leaf("ok"), fragile("bad", true)), |msg: &str| {
+                leaf(format!("error: {msg}"))
+            }),
+            leaf("footer"),
+        )
+    });
+    assert_eq!(root.child_names(), ["error: bad broke", "footer"]);
+    assert_eq!(take_errors(), ["view panicked: bad broke"]);
+
+    app.destroy();
+    assert!(root.children().is_empty());
+}
+
+#[test]
+fn panics_while_rebuilding_show_the_fallback() {
+    let root = MockNode::new("Root", "root");
+    let mut app = App::<_, Mock>::attach(root.clone(), || {
+        error_boundary((leaf("ok"), fragile("bad", false)), |msg: &str| {
+            leaf(format!("error: {msg}"))
+        })
+    });
+    assert_eq!(root.child_names(), ["ok", "bad"]);
+    let nodes = root.children();
+
+    app.update(|boundary| boundary.view_mut().1.panics = true);
+    assert_eq!(root.child_names(), ["error: bad broke"]);
+    assert!(
+        nodes
+            .iter()
+            .filter(|n| !n.is_marker())
+            .all(MockNode::is_freed)
+    );
+    take_errors();
+}
+
+#[test]
+fn reset_builds_the_child_again() {
+    let root = MockNode::new("Root", "root");
+    let mut app = App::<_, Mock>::attach(root.clone(), || {
+        error_boundary(fragile("flaky", true), |_: &str| leaf("fallback"))
+    });
+    assert_eq!(root.child_names(), ["fallback"]);
+
+    // Rebuilding without a reset keeps the fallback.
+    app.update(|boundary| boundary.view_mut().panics = false);
+    assert_eq!(root.child_names(), ["fallback"]);
+
+    app.update(|boundary| boundary.reset());
+    assert_eq!(root.child_names(), ["flaky"]);
+    take_errors();
+}
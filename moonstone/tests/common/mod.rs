@@ -0,0 +1,12 @@
+use moonstone::{
+    App, View,
+    mock::{Mock, MockNode, reset_stats},
+};
+
+/// Builds `view` under a new root node, with the stats reset after the build.
+pub fn mount<T: View<Mock>>(view: T) -> (MockNode, App<T, Mock>) {
+    let root = MockNode::new("Root", "root");
+    let app = App::attach(root.clone(), || view);
+    reset_stats();
+    (root, app)
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use std::cell::{Cell, RefCell};
+
+use common::mount;
+use moonstone::{
+    Element,
+    mock::{Mock, MockNode, el, reset_stats, stats},
+    reactive::Signal,
+};
+
+thread_local! {
+    static WIDTH_SET: Cell<usize> = const { Cell::new(0) };
+    static CALLS: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+}
+
+fn set_width(node: &mut MockNode, width: u32) {
+    WIDTH_SET.set(WIDTH_SET.get() + 1);
+    node.set_prop("width", width.to_string());
+}
+
+fn label(text: &str, width: u32) -> Element<MockNode, (), Mock> {
+    el().prop("name", "label")
+        .prop("text", text)
+        .set(set_width, width)
+}
+
+#[test]
+fn props_are_applied_on_build() {
+    let (root, _app) = mount(label("hi", 10));
+    let node = &root.children()[0];
+    assert_eq!(node.name(), "label");
+    assert_eq!(node.prop("text").as_deref(), Some("hi"));
+    assert_eq!(node.prop("width").as_deref(), Some("10"));
+    assert_eq!(WIDTH_SET.get(), 1);
+}
+
+#[test]
+fn unchanged_props_are_not_reapplied() {
+    let (root, mut app) = mount(label("hi", 10));
+    let node = root.children()[0].clone();
+
+    app.update(|v| *v = label("hi", 10));
+    assert_eq!(stats().props_set, 0);
+    assert_eq!(WIDTH_SET.get(), 1);
+
+    app.update(|v| *v = label("bye", 10));
+    assert_eq!(stats().props_set, 1);
+    assert_eq!(node.prop("text").as_deref(), Some("bye"));
+    assert_eq!(WIDTH_SET.get(), 1);
+
+    app.update(|v| *v = label("bye", 20));
+    assert_eq!(node.prop("width").as_deref(), Some("20"));
+    assert_eq!(WIDTH_SET.get(), 2);
+    assert_eq!(root.children(), [node]);
+}
+
+#[test]
+fn props_are_compared_by_index() {
+    let swapped = |first: &str, second: &str| {
+        el().prop(first.to_owned(), "1")
+            .prop(second.to_owned(), "1")
+    };
+    let (_root, mut app) = mount(swapped("a", "b"));
+
+    app.update(|v| *v = swapped("b", "a"));
+    assert_eq!(stats().props_set, 2);
+}
+
+#[test]
+fn setters_with_captures_are_always_reapplied() {
+    let with_unit = |unit: &'static str| {
+        el().set(
+            move |node: &mut MockNode, width: u32| node.set_prop("width", format!("{width}{unit}")),
+            10,
+        )
+    };
+    let (root, mut app) = mount(with_unit("px"));
+
+    app.update(|v| *v = with_unit("em"));
+    assert_eq!(root.children()[0].prop("width").as_deref(), Some("10em"));
+}
+
+fn button(signal: &str, handler: &'static str) -> Element<MockNode, (), Mock> {
+    el().on(signal.to_owned(), move |args| {
+        CALLS.with_borrow_mut(|calls| calls.push(format!("{handler}{args:?}")));
+    })
+}
+
+#[test]
+fn handlers_are_connected_on_build() {
+    let (root, _app) = mount(button("pressed", "first"));
+    let node = &root.children()[0];
+    assert_eq!(node.connections("pressed"), 1);
+
+    node.emit("pressed", &["x"]);
+    assert_eq!(CALLS.take(), ["first[\"x\"]"]);
+}
+
+#[test]
+fn rebuilding_swaps_the_handler_without_reconnecting() {
+    let (root, mut app) = mount(button("pressed", "first"));
+    let node = root.children()[0].clone();
+
+    app.update(|v| *v = button("pressed", "second"));
+    assert_eq!((stats().connected, stats().disconnected), (0, 0));
+
+    node.emit("pressed", &[]);
+    assert_eq!(CALLS.take(), ["second[]"]);
+}
+
+#[test]
+fn changing_the_signal_reconnects() {
+    let (root, mut app) = mount(button("pressed", "first"));
+    let node = root.children()[0].clone();
+
+    app.update(|v| *v = button("toggled", "second"));
+    assert_eq!((stats().connected, stats().disconnected), (1, 1));
+    assert_eq!(node.connections("pressed"), 0);
+
+    node.emit("pressed", &[]);
+    node.emit("toggled", &["true"]);
+    assert_eq!(CALLS.take(), ["second[\"true\"]"]);
+}
+
+#[test]
+fn teardown_disconnects() {
+    let (root, app) = mount(button("pressed", "first"));
+    let node = root.children()[0].clone();
+
+    app.destroy();
+    assert_eq!(stats().disconnected, 1);
+    assert_eq!(node.connections("pressed"), 0);
+    assert!(node.is_freed());
+}
+
+fn bound(signal: &Signal<u32>) -> Element<MockNode, (), Mock> {
+    let signal = signal.clone();
+    el().bind("count", move || signal.get().to_string())
+}
+
+#[test]
+fn bindings_follow_their_signal() {
+    let count = Signal::new(1);
+    let (root, _app) = mount(bound(&count));
+    let node = &root.children()[0];
+    assert_eq!(node.prop("count").as_deref(), Some("1"));
+
+    count.set(2);
+    assert_eq!(node.prop("count").as_deref(), Some("2"));
+}
+
+#[test]
+fn swapped_bindings_follow_the_new_signal() {
+    let (old, new) = (Signal::new(1), Signal::new(1));
+    let (root, mut app) = mount(bound(&old));
+    let node = root.children()[0].clone();
+
+    // The same value isn't set again.
+    app.update(|v| *v = bound(&new));
+    assert_eq!(stats().props_set, 0);
+
+    new.set(5);
+    assert_eq!(node.prop("count").as_deref(), Some("5"));
+
+    reset_stats();
+    old.set(9);
+    assert_eq!(stats().props_set, 0);
+    assert_eq!(node.prop("count").as_deref(), Some("5"));
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use std::collections::{BTreeMap, HashMap};
+
+use common::mount;
+use moonstone::{
+    SortedMap,
+    mock::{Leaf, leaf},
+};
+
+fn items<C: FromIterator<(u32, Leaf)>>(keys: &[u32]) -> C {
+    keys.iter().map(|k| (*k, leaf(k.to_string()))).collect()
+}
+
+#[test]
+fn btree_map_insert_and_remove_keep_existing_nodes() {
+    let (root, mut app) = mount(items::<BTreeMap<_, _>>(&[1, 3, 5]));
+    let kept = root.children();
+
+    app.update(|map| {
+        map.insert(2, leaf("2"));
+        map.remove(&5);
+    });
+    assert_eq!(root.child_names(), ["1", "2", "3"]);
+    assert_eq!(root.children()[0], kept[0]);
+    assert_eq!(root.children()[2], kept[1]);
+    assert!(kept[2].is_freed());
+}
+
+#[test]
+fn sorted_map_renders_in_key_order() {
+    let (root, mut app) = mount(SortedMap(items::<HashMap<_, _>>(&[4, 1, 3])));
+    assert_eq!(root.child_names(), ["1", "3", "4"]);
+    let kept = root.children();
+
+    app.update(|map| {
+        map.insert(2, leaf("2"));
+        map.insert(0, leaf("0"));
+        map.remove(&3);
+    });
+    assert_eq!(root.child_names(), ["0", "1", "2", "4"]);
+    assert_eq!(root.children()[1], kept[0]);
+    assert_eq!(root.children()[3], kept[2]);
+    assert!(kept[1].is_freed());
+
+    app.update(|map| *map = SortedMap(items(&[9, 4, 0])));
+    assert_eq!(root.child_names(), ["0", "4", "9"]);
+}
+
+#[cfg(feature = "indexmap")]
+#[test]
+fn index_map_keeps_its_order_and_nodes() {
+    use indexmap::IndexMap;
+
+    let (root, mut app) = mount(items::<IndexMap<_, _>>(&[3, 1, 2]));
+    assert_eq!(root.child_names(), ["3", "1", "2"]);
+    let kept = root.children();
+
+    app.update(|map| {
+        map.shift_remove(&1);
+        map.insert(0, leaf("0"));
+    });
+    assert_eq!(root.child_names(), ["3", "2", "0"]);
+    assert_eq!(root.children()[0], kept[0]);
+    assert_eq!(root.children()[1], kept[2]);
+    assert!(kept[1].is_freed());
+}
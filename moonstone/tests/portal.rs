@@ -0,0 +1,42 @@
+use moonstone::{
+    App, Portal,
+    mock::{Mock, MockNode, leaf},
+};
+
+#[test]
+fn portals_build_under_their_target() {
+    let root = MockNode::new("Root", "root");
+    let overlay = MockNode::new("Overlay", "overlay");
+    let mut app = App::<_, Mock>::attach(root.clone(), || {
+        (
+            leaf("header"),
+            Portal::<_, Mock>::new(overlay.clone(), leaf("popup")),
+            leaf("footer"),
+        )
+    });
+    assert_eq!(root.child_names(), ["header", "footer"]);
+    assert_eq!(overlay.child_names(), ["popup"]);
+
+    app.update(|(_, _, footer)| *footer = leaf("new footer"));
+    assert_eq!(root.child_names(), ["header", "new footer"]);
+    assert_eq!(overlay.child_names(), ["popup"]);
+
+    // The popup goes away with the view that owns it.
+    app.destroy();
+    assert!(overlay.children().is_empty());
+}
+
+#[test]
+fn changing_the_target_moves_the_child() {
+    let root = MockNode::new("Root", "root");
+    let first = MockNode::new("Overlay", "first");
+    let second = MockNode::new("Overlay", "second");
+    let mut app = App::<_, Mock>::attach(root.clone(), || {
+        Portal::<_, Mock>::new(first.clone(), leaf("popup"))
+    });
+
+    app.update(|portal| *portal = Portal::new(second.clone(), leaf("popup")));
+    assert!(first.children().is_empty());
+    assert_eq!(second.child_names(), ["popup"]);
+    app.destroy();
+}
@@ -0,0 +1,74 @@
+use moonstone::{
+    Program,
+    mock::{Leaf, Mock, MockNode, leaf, reset_stats, stats},
+};
+
+enum Msg {
+    Add(&'static str),
+    Remove(&'static str),
+}
+
+fn todo_list(items: &[&'static str]) -> Vec<(&'static str, Leaf)> {
+    items.iter().map(|item| (*item, leaf(*item))).collect()
+}
+
+fn program(root: &MockNode) -> Program<Vec<&'static str>, Msg, Vec<(&'static str, Leaf)>, Mock> {
+    Program::attach(
+        root.clone(),
+        vec!["milk"],
+        |items, _| todo_list(items),
+        |items, msg| match msg {
+            Msg::Add(item) => items.push(item),
+            Msg::Remove(item) => items.retain(|i| *i != item),
+        },
+    )
+}
+
+#[test]
+fn messages_are_applied_on_process() {
+    let root = MockNode::new("Root", "root");
+    let mut program = program(&root);
+    let messenger = program.messenger();
+
+    messenger.send(Msg::Add("eggs"));
+    messenger.send(Msg::Add("bread"));
+    assert_eq!(root.child_names(), ["milk"]);
+
+    assert!(program.process());
+    assert_eq!(program.state(), &["milk", "eggs", "bread"]);
+    assert_eq!(root.child_names(), ["milk", "eggs", "bread"]);
+
+    messenger.send(Msg::Remove("milk"));
+    assert!(program.process());
+    assert_eq!(root.child_names(), ["eggs", "bread"]);
+}
+
+#[test]
+fn process_without_messages_does_not_rebuild() {
+    let root = MockNode::new("Root", "root");
+    let mut program = program(&root);
+    reset_stats();
+
+    assert!(!program.process());
+    assert_eq!(stats(), Default::default());
+}
+
+#[test]
+fn callbacks_send_clones_of_their_message() {
+    let root = MockNode::new("Root", "root");
+    let mut program = Program::<_, _, _, Mock>::attach(
+        root.clone(),
+        0,
+        |count, _| leaf(count.to_string()),
+        |count, step: u32| *count += step,
+    );
+
+    let increment = program.messenger().callback(2);
+    increment();
+    increment();
+    program.process();
+    assert_eq!(root.child_names(), ["4"]);
+
+    program.destroy();
+    assert!(root.children().is_empty());
+}
@@ -0,0 +1,149 @@
+use std::{cell::RefCell, rc::Rc};
+
+use moonstone::{
+    App, Indexed, fragment,
+    mock::{Mock, MockNode, leaf, reset_stats, stats},
+    reactive::{Effect, Memo, Signal, batch, dynamic, untracked},
+};
+
+fn log() -> (Rc<RefCell<Vec<i32>>>, impl Fn(i32)) {
+    let log = Rc::new(RefCell::new(vec![]));
+    let push = {
+        let log = log.clone();
+        move |v| log.borrow_mut().push(v)
+    };
+    (log, push)
+}
+
+#[test]
+fn effects_rerun_when_a_signal_they_read_changes() {
+    let count = Signal::new(1);
+    let (log, push) = log();
+    let _effect = Effect::new({
+        let count = count.clone();
+        move || push(count.get())
+    });
+
+    count.set(2);
+    count.update(|c| *c += 1);
+    assert_eq!(*log.borrow(), [1, 2, 3]);
+}
+
+#[test]
+fn effects_only_track_the_signals_of_their_last_run() {
+    let show = Signal::new(true);
+    let a = Signal::new(0);
+    let (log, push) = log();
+    let _effect = Effect::new({
+        let (show, a) = (show.clone(), a.clone());
+        move || push(if show.get() { a.get() } else { -1 })
+    });
+
+    show.set(false);
+    a.set(5);
+    assert_eq!(*log.borrow(), [0, -1]);
+}
+
+#[test]
+fn untracked_reads_do_not_subscribe() {
+    let a = Signal::new(0);
+    let (log, push) = log();
+    let _effect = Effect::new({
+        let a = a.clone();
+        move || push(untracked(|| a.get()))
+    });
+
+    a.set(1);
+    assert_eq!(*log.borrow(), [0]);
+}
+
+#[test]
+fn dropped_effects_stop() {
+    let a = Signal::new(0);
+    let (log, push) = log();
+    let effect = Effect::new({
+        let a = a.clone();
+        move || push(a.get())
+    });
+
+    drop(effect);
+    a.set(1);
+    assert_eq!(*log.borrow(), [0]);
+}
+
+#[test]
+fn batches_run_each_effect_once() {
+    let a = Signal::new(0);
+    let b = Signal::new(0);
+    let (log, push) = log();
+    let _effect = Effect::new({
+        let (a, b) = (a.clone(), b.clone());
+        move || push(a.get() + b.get())
+    });
+
+    batch(|| {
+        a.set(1);
+        b.set(2);
+    });
+    assert_eq!(*log.borrow(), [0, 3]);
+}
+
+#[test]
+fn memos_only_notify_when_their_value_changes() {
+    let n = Signal::new(1);
+    let parity = Memo::new({
+        let n = n.clone();
+        move || n.get() % 2
+    });
+    let (log, push) = log();
+    let _effect = Effect::new({
+        let parity = parity.clone();
+        move || push(parity.get())
+    });
+
+    n.set(3);
+    n.set(4);
+    assert_eq!(*log.borrow(), [1, 0]);
+}
+
+#[test]
+fn dynamic_views_rebuild_without_their_parent() {
+    let items = Signal::new(vec!["a".to_owned()]);
+    let root = MockNode::new("Root", "root");
+    let app = App::<_, Mock>::attach(root.clone(), || {
+        let items = items.clone();
+        (
+            leaf("header"),
+            dynamic(move || items.with(|items| items.iter().map(leaf).collect::<Indexed<_>>())),
+            leaf("footer"),
+        )
+    });
+    let header = root.children()[0].clone();
+    reset_stats();
+
+    items.update(|items| items.push("b".to_owned()));
+    assert_eq!(root.child_names(), ["header", "a", "b", "footer"]);
+    assert_eq!(root.children()[0], header);
+    assert_eq!(stats().added, 1);
+
+    app.destroy();
+    assert!(root.children().is_empty());
+
+    // The view is gone, so it no longer follows the signal.
+    items.set(vec![]);
+    assert!(root.children().is_empty());
+}
+
+#[test]
+fn dynamic_views_keep_their_anchor_strategy() {
+    let shown = Signal::new(false);
+    let root = MockNode::new("Root", "root");
+    let _app = App::<_, Mock>::attach(root.clone(), || {
+        let shown = shown.clone();
+        fragment(dynamic(move || shown.get().then(|| Some(leaf("x")))))
+    });
+
+    shown.set(true);
+    assert_eq!(root.child_names(), ["x"]);
+    assert!(root.children().iter().all(|c| !c.is_marker()));
+}
@@ -0,0 +1,215 @@
+mod common;
+
+use std::rc::Rc;
+
+use common::mount;
+use moonstone::{
+    AnchorStrategy, AnyView, DebugKeys, DuplicateKeyPolicy, Indexed, OneOf2, anchored, fragment,
+    memo,
+    mock::{Leaf, Mock, leaf, reset_stats, stats, take_errors},
+    set_duplicate_key_policy,
+};
+
+fn keyed(keys: &[u32]) -> Vec<(u32, Leaf)> {
+    keys.iter().map(|k| (*k, leaf(k.to_string()))).collect()
+}
+
+#[test]
+fn option_toggles_in_place() {
+    let (root, mut app) = mount((leaf("a"), None::<Leaf>, leaf("c")));
+    assert_eq!(root.child_names(), ["a", "c"]);
+
+    app.update(|(_, b, _)| *b = Some(leaf("b")));
+    assert_eq!(root.child_names(), ["a", "b", "c"]);
+
+    app.update(|(_, b, _)| *b = Some(leaf("B")));
+    assert_eq!(root.child_names(), ["a", "B", "c"]);
+
+    app.update(|(_, b, _)| *b = None);
+    assert_eq!(root.child_names(), ["a", "c"]);
+    assert_eq!(stats().freed, 1);
+}
+
+#[test]
+fn result_switches_branches() {
+    let (root, mut app) = mount((Ok::<Leaf, Leaf>(leaf("ok")), leaf("end")));
+    app.update(|(r, _)| *r = Err(leaf("err")));
+    assert_eq!(root.child_names(), ["err", "end"]);
+    app.update(|(r, _)| *r = Ok(leaf("ok")));
+    assert_eq!(root.child_names(), ["ok", "end"]);
+}
+
+#[test]
+fn indexed_only_touches_the_tail() {
+    let (root, mut app) = mount(Indexed(vec![leaf("a"), leaf("b")]));
+    let before = root.children();
+
+    app.update(|list| list.push(leaf("c")));
+    assert_eq!(root.child_names(), ["a", "b", "c"]);
+    assert_eq!(root.children()[..2], before[..2]);
+    assert_eq!(stats().added, 1);
+
+    reset_stats();
+    app.update(|list| list.truncate(1));
+    assert_eq!(root.child_names(), ["a"]);
+    assert_eq!(stats().freed, 2);
+}
+
+#[test]
+fn keyed_reorder_moves_as_few_nodes_as_possible() {
+    let (root, mut app) = mount(keyed(&[1, 2, 3, 4, 5]));
+
+    // Moving one item to the front keeps the other four in place.
+    app.update(|list| *list = keyed(&[5, 1, 2, 3, 4]));
+    assert_eq!(root.child_names(), ["5", "1", "2", "3", "4"]);
+    assert_eq!(stats().moved, 1);
+    assert_eq!(stats().added, 0);
+
+    reset_stats();
+    app.update(|list| *list = keyed(&[4, 3, 2, 1, 5]));
+    assert_eq!(root.child_names(), ["4", "3", "2", "1", "5"]);
+    assert_eq!(stats().added, 0);
+}
+
+#[test]
+fn keyed_insert_and_remove_keep_existing_nodes() {
+    let (root, mut app) = mount((keyed(&[1, 2, 3]), leaf("end")));
+    let kept = root.children()[1].clone();
+
+    app.update(|(list, _)| *list = keyed(&[0, 2, 4, 3]));
+    assert_eq!(root.child_names(), ["0", "2", "4", "3", "end"]);
+    assert!(root.children().contains(&kept));
+    assert_eq!(stats().freed, 1);
+
+    app.update(|(list, _)| list.clear());
+    assert_eq!(root.child_names(), ["end"]);
+}
+
+#[test]
+fn keyed_items_with_several_nodes_move_together() {
+    let pair = |k: u32| (k, (leaf(format!("{k}a")), leaf(format!("{k}b"))));
+    let (root, mut app) = mount(vec![pair(1), pair(2), pair(3)]);
+
+    app.update(|list| list.rotate_left(1));
+    assert_eq!(root.child_names(), ["2a", "2b", "3a", "3b", "1a", "1b"]);
+}
+
+#[test]
+fn duplicate_keys_fall_back_to_positions() {
+    set_duplicate_key_policy(DuplicateKeyPolicy::Fallback);
+    let (root, mut app) = mount(keyed(&[1, 2]));
+
+    app.update(|list| *list = keyed(&[1, 1, 2]));
+    assert_eq!(root.child_names(), ["1", "1", "2"]);
+    let errors = take_errors();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("duplicate key of type `u32` at indices 0 and 1"));
+
+    app.update(|list| *list = keyed(&[2, 1]));
+    assert_eq!(root.child_names(), ["2", "1"]);
+    assert!(take_errors().is_empty());
+}
+
+#[test]
+fn debug_keys_are_named_in_the_report() {
+    set_duplicate_key_policy(DuplicateKeyPolicy::Fallback);
+    let (root, mut app) = mount(DebugKeys(keyed(&[1, 2])));
+
+    app.update(|list| list.0 = keyed(&[2, 3, 3]));
+    assert_eq!(root.child_names(), ["2", "3", "3"]);
+    let errors = take_errors();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("duplicate key 3 of type `u32` at indices 1 and 2"));
+}
+
+#[test]
+fn keys_without_debug_are_reported_by_type() {
+    #[derive(Clone, PartialEq, Eq, Hash)]
+    struct Id(u32);
+
+    set_duplicate_key_policy(DuplicateKeyPolicy::Fallback);
+    let (root, _app) = mount(vec![(Id(1), leaf("a")), (Id(1), leaf("b"))]);
+    assert_eq!(root.child_names(), ["a", "b"]);
+    let errors = take_errors();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("duplicate key of type `reconcile::"));
+}
+
+#[test]
+#[should_panic(expected = "duplicate key 7 of type `u32` at indices 1 and 2")]
+fn duplicate_keys_panic() {
+    set_duplicate_key_policy(DuplicateKeyPolicy::Panic);
+    mount(DebugKeys(keyed(&[3, 7, 7])));
+}
+
+#[test]
+fn fragments_insert_no_markers() {
+    let (root, mut app) = mount(fragment((leaf("a"), None::<Leaf>, keyed(&[1, 2]))));
+    assert!(root.children().iter().all(|c| !c.is_marker()));
+
+    app.update(|v| *v = fragment((leaf("a"), Some(leaf("b")), keyed(&[2, 1]))));
+    assert_eq!(root.child_names(), ["a", "b", "2", "1"]);
+    assert_eq!(stats().markers, 0);
+}
+
+#[test]
+fn marker_anchors_keep_empty_views_in_place() {
+    let (root, mut app) = mount(anchored(AnchorStrategy::Marker, (None::<Leaf>, leaf("b"))));
+    assert_eq!(root.children().len(), 2);
+
+    app.update(|v| *v = anchored(AnchorStrategy::Marker, (Some(leaf("a")), leaf("b"))));
+    assert_eq!(root.child_names(), ["a", "b"]);
+}
+
+#[test]
+fn one_of_replaces_the_inner_view() {
+    let (root, mut app) = mount((OneOf2::<Leaf, (Leaf, Leaf)>::A(leaf("a")), leaf("end")));
+
+    app.update(|(v, _)| *v = OneOf2::B((leaf("b1"), leaf("b2"))));
+    assert_eq!(root.child_names(), ["b1", "b2", "end"]);
+    assert_eq!(stats().freed, 1);
+}
+
+#[test]
+fn any_view_rebuilds_same_type_and_replaces_other_types() {
+    let (root, mut app) = mount(Box::new(leaf("a")) as Box<dyn AnyView<Mock>>);
+    let first = root.children()[0].clone();
+
+    app.update(|v| *v = Box::new(leaf("b")));
+    assert_eq!(root.children()[0], first);
+    assert_eq!(root.child_names(), ["b"]);
+
+    app.update(|v| *v = Box::new((leaf("c"), leaf("d"))));
+    assert_eq!(root.child_names(), ["c", "d"]);
+    assert!(first.is_freed());
+}
+
+#[test]
+fn pointer_views_forward_to_their_contents() {
+    let (root, mut app) = mount(Rc::new(leaf("a")));
+    app.update(|v| *v = Rc::new(leaf("b")));
+    assert_eq!(root.child_names(), ["b"]);
+}
+
+#[test]
+fn memo_skips_rebuilds_with_equal_deps() {
+    let build = |n: u32| {
+        memo(n, |n| {
+            Indexed((0..*n).map(|i| leaf(i.to_string())).collect())
+        })
+    };
+    let (root, mut app) = mount(build(2));
+
+    app.update(|v| *v = build(2));
+    assert_eq!(stats(), Default::default());
+
+    app.update(|v| *v = build(3));
+    assert_eq!(root.child_names(), ["0", "1", "2"]);
+}
+
+#[test]
+fn destroy_frees_every_node() {
+    let (root, app) = mount((leaf("a"), Some(keyed(&[1, 2])), Indexed(vec![leaf("b")])));
+    app.destroy();
+    assert!(root.children().is_empty());
+}
@@ -0,0 +1,33 @@
+use std::{cell::RefCell, rc::Rc};
+
+use moonstone::scheduler::{flush, has_pending, schedule};
+
+#[test]
+fn rebuilds_with_the_same_key_run_once() {
+    let runs = Rc::new(RefCell::new(vec![]));
+    for (key, name) in [(1, "a"), (2, "b"), (1, "c")] {
+        let runs = runs.clone();
+        schedule(key, move || runs.borrow_mut().push(name));
+    }
+    assert!(has_pending());
+
+    flush();
+    assert_eq!(*runs.borrow(), ["a", "b"]);
+    assert!(!has_pending());
+}
+
+#[test]
+fn rebuilds_scheduled_while_flushing_wait_for_the_next_flush() {
+    let runs = Rc::new(RefCell::new(0));
+    let inner = runs.clone();
+    schedule(1, move || {
+        *inner.borrow_mut() += 1;
+        let inner = inner.clone();
+        schedule(1, move || *inner.borrow_mut() += 1);
+    });
+
+    flush();
+    assert_eq!(*runs.borrow(), 1);
+    flush();
+    assert_eq!(*runs.borrow(), 2);
+}
@@ -0,0 +1,26 @@
+use moonstone::{
+    App, Indexed,
+    mock::{Leaf, Mock, MockNode, leaf},
+    snapshot::Snapshot,
+};
+
+fn panel() -> MockNode {
+    let panel = MockNode::new("Panel", "panel");
+    panel.set_prop("visible", "true");
+    App::<_, Mock>::attach(panel.clone(), || (leaf("title"), Some(leaf("body"))));
+    panel
+}
+
+#[test]
+fn renders_classes_names_and_props() {
+    let root = MockNode::new("Root", "root");
+    let _app = App::<_, Mock>::attach(root.clone(), || {
+        (panel(), Indexed(vec![leaf("a"), leaf("b")]), None::<Leaf>)
+    });
+
+    let tree = Snapshot::new().prop("visible").render::<Mock>(&root);
+    moonstone::assert_snapshot!("tree", tree);
+
+    let tree = Snapshot::new().markers(true).render::<Mock>(&root);
+    moonstone::assert_snapshot!("tree_with_markers", tree);
+}
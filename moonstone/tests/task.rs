@@ -0,0 +1,214 @@
+use std::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+use moonstone::{
+    App, Messenger, Program, fragment,
+    mock::{Mock, MockNode, leaf},
+    task::{spawn, suspense, tick, unblock},
+};
+
+/// A future that resolves once `complete` is called on its sender.
+struct Pending<T>(Rc<RefCell<(Option<T>, Option<Waker>)>>);
+
+impl<T> Future for Pending<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut shared = self.0.borrow_mut();
+        match shared.0.take() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                shared.1 = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Sender<T>(Rc<RefCell<(Option<T>, Option<Waker>)>>);
+
+impl<T> Sender<T> {
+    fn complete(&self, value: T) {
+        let mut shared = self.0.borrow_mut();
+        shared.0 = Some(value);
+        if let Some(waker) = shared.1.take() {
+            waker.wake();
+        }
+    }
+}
+
+fn pending<T>() -> (Sender<T>, Pending<T>) {
+    let shared = Rc::new(RefCell::new((None, None)));
+    (Sender(shared.clone()), Pending(shared))
+}
+
+#[test]
+fn tasks_run_when_woken() {
+    let (sender, future) = pending::<i32>();
+    let result = Rc::new(RefCell::new(None));
+    let task = spawn({
+        let result = result.clone();
+        async move { *result.borrow_mut() = Some(future.await) }
+    });
+
+    assert!(tick());
+    assert!(!task.is_finished());
+
+    sender.complete(3);
+    tick();
+    assert_eq!(*result.borrow(), Some(3));
+    assert!(task.is_finished());
+}
+
+#[test]
+fn dropped_tasks_are_cancelled() {
+    let (sender, future) = pending::<i32>();
+    let result = Rc::new(RefCell::new(None));
+    let task = spawn({
+        let result = result.clone();
+        async move { *result.borrow_mut() = Some(future.await) }
+    });
+    tick();
+
+    drop(task);
+    sender.complete(3);
+    tick();
+    assert_eq!(*result.borrow(), None);
+}
+
+#[test]
+fn unblocked_work_resolves_on_this_thread() {
+    let result = Rc::new(RefCell::new(None));
+    spawn({
+        let result = result.clone();
+        async move { *result.borrow_mut() = Some(unblock(|| 6 * 7).await) }
+    })
+    .detach();
+
+    while result.borrow().is_none() {
+        tick();
+        std::thread::yield_now();
+    }
+    assert_eq!(*result.borrow(), Some(42));
+}
+
+#[test]
+fn suspense_shows_the_fallback_until_resolved() {
+    let (sender, future) = pending::<String>();
+    let future = RefCell::new(Some(future));
+    let root = MockNode::new("Root", "root");
+    let app = App::<_, Mock>::attach(root.clone(), || {
+        (
+            suspense(
+                (),
+                |_: &()| future.borrow_mut().take().unwrap(),
+                leaf("loading"),
+                |name: &String| leaf(name),
+            ),
+            leaf("footer"),
+        )
+    });
+    assert_eq!(root.child_names(), ["loading", "footer"]);
+
+    sender.complete("loaded".to_owned());
+    tick();
+    assert_eq!(root.child_names(), ["loaded", "footer"]);
+
+    app.destroy();
+    assert!(root.children().is_empty());
+}
+
+#[test]
+fn suspense_restarts_when_its_deps_change() {
+    let senders = Rc::new(RefCell::new(vec![]));
+    let root = MockNode::new("Root", "root");
+    let mut program = Program::<_, i32, _, Mock>::attach(
+        root.clone(),
+        1,
+        {
+            let senders = senders.clone();
+            move |n: &i32, _: &Messenger<i32>| {
+                let senders = senders.clone();
+                suspense(
+                    *n,
+                    move |n: &i32| {
+                        let (sender, future) = pending::<String>();
+                        senders.borrow_mut().push(sender);
+                        let n = *n;
+                        async move { format!("{}-{n}", future.await) }
+                    },
+                    leaf("loading"),
+                    |name: &String| leaf(name),
+                )
+            }
+        },
+        |n, msg| *n = msg,
+    );
+    tick();
+    senders.borrow()[0].complete("item".to_owned());
+    tick();
+    assert_eq!(root.child_names(), ["item-1"]);
+
+    // Rebuilding with the same deps keeps the result.
+    program.messenger().send(1);
+    program.process();
+    assert_eq!(root.child_names(), ["item-1"]);
+    assert_eq!(senders.borrow().len(), 1);
+
+    program.messenger().send(2);
+    program.process();
+    assert_eq!(root.child_names(), ["loading"]);
+    tick();
+    senders.borrow()[1].complete("item".to_owned());
+    tick();
+    assert_eq!(root.child_names(), ["item-2"]);
+
+    program.destroy();
+}
+
+#[test]
+fn torn_down_suspense_cancels_its_future() {
+    let (sender, future) = pending::<String>();
+    let future = RefCell::new(Some(future));
+    let root = MockNode::new("Root", "root");
+    let app = App::<_, Mock>::attach(root.clone(), || {
+        suspense(
+            (),
+            |_: &()| future.borrow_mut().take().unwrap(),
+            leaf("loading"),
+            |name: &String| leaf(name),
+        )
+    });
+    tick();
+
+    app.destroy();
+    sender.complete("loaded".to_owned());
+    assert!(!tick());
+    assert!(root.children().is_empty());
+}
+
+#[test]
+fn resolved_suspense_keeps_its_anchor_strategy() {
+    let (sender, future) = pending::<String>();
+    let future = RefCell::new(Some(future));
+    let root = MockNode::new("Root", "root");
+    let _app = App::<_, Mock>::attach(root.clone(), || {
+        fragment(suspense(
+            (),
+            |_: &()| future.borrow_mut().take().unwrap(),
+            leaf("loading"),
+            |name: &String| Some(leaf(name)),
+        ))
+    });
+
+    sender.complete("loaded".to_owned());
+    tick();
+    assert_eq!(root.child_names(), ["loaded"]);
+    assert!(root.children().iter().all(|c| !c.is_marker()));
+}
@@ -0,0 +1,4 @@
+#[test]
+fn viewtype_errors() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,10 @@
+use moonstone::viewtype;
+
+viewtype! {
+    #[class(init)]
+    struct Menu: godot::classes::VBoxContainer {
+        view button: godot::obj::Gd<godot::classes::Button>,
+    }
+}
+
+fn main() {}
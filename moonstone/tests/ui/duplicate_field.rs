@@ -0,0 +1,12 @@
+use moonstone::viewtype;
+
+viewtype! {
+    struct Menu: godot::classes::VBoxContainer {
+        view button: godot::obj::Gd<godot::classes::Button>,
+        view row: godot::classes::VBoxContainer {
+            view button: godot::obj::Gd<godot::classes::Button>,
+        }
+    }
+}
+
+fn main() {}
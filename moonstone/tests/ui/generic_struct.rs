@@ -0,0 +1,9 @@
+use moonstone::viewtype;
+
+viewtype! {
+    struct List<T>: godot::classes::VBoxContainer {
+        items: Vec<T>,
+    }
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use moonstone::viewtype;
+
+viewtype! {
+    struct Menu {
+        view button: godot::obj::Gd<godot::classes::Button>,
+    }
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use moonstone::viewtype;
+
+viewtype! {
+    struct Menu: godot::classes::VBoxContainer {
+        row: godot::classes::VBoxContainer {
+            view button: godot::obj::Gd<godot::classes::Button>,
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use moonstone::viewtype;
+
+viewtype! {
+    struct Menu: godot::classes::VBoxContainer {
+        view base: godot::obj::Gd<godot::classes::Button>,
+    }
+}
+
+viewtype! {
+    struct Toolbar: godot::classes::VBoxContainer {
+        view __parent: godot::obj::Gd<godot::classes::Button>,
+    }
+}
+
+fn main() {}
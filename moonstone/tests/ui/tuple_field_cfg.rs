@@ -0,0 +1,9 @@
+use moonstone::viewtype;
+
+viewtype! {
+    enum Shown {
+        Pair(#[cfg(any())] godot::obj::Gd<godot::classes::Label>, godot::obj::Gd<godot::classes::Button>),
+    }
+}
+
+fn main() {}
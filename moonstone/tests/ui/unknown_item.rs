@@ -0,0 +1,7 @@
+use moonstone::viewtype;
+
+viewtype! {
+    fn foo() {}
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use moonstone::viewtype;
+
+viewtype! {
+    struct Menu: godot::classes::VBoxContainer {
+        #[export]
+        view button: godot::obj::Gd<godot::classes::Button>,
+    }
+}
+
+fn main() {}
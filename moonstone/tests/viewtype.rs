@@ -0,0 +1,103 @@
+use godot::{
+    classes::{Button, Label, LineEdit, VBoxContainer},
+    prelude::*,
+};
+use moonstone::{View, viewtype};
+
+viewtype! {
+    pub enum Either<L, R> {
+        Left(L),
+        Right(R),
+    }
+}
+
+viewtype! {
+    pub enum Labelled<T> where T: Clone {
+        Label(Gd<Label>),
+        Items(Vec<(T, Gd<Button>)>),
+    }
+}
+
+viewtype! {
+    pub enum Field {
+        Empty,
+        Edit { label: Gd<Label>, input: Gd<LineEdit> },
+        Pair(Gd<Label>, Option<Gd<Button>>),
+    }
+}
+
+viewtype! {
+    pub enum Nothing {
+        A,
+        B,
+    }
+}
+
+viewtype! {
+    /// A form field.
+    #[derive(Clone, Debug)]
+    pub enum Documented {
+        /// Shows nothing.
+        Empty,
+        #[cfg(any())]
+        Missing(Gd<Label>),
+        Shown(Gd<Label>),
+        Edit {
+            #[cfg(any())]
+            label: Gd<Label>,
+            /// Where the value is typed.
+            input: Gd<LineEdit>,
+        },
+    }
+}
+
+viewtype! {
+    /// A menu with a button.
+    #[class(tool)]
+    pub struct Menu: VBoxContainer {
+        /// The button at the top.
+        pub view button: Gd<Button>,
+        #[cfg(any())]
+        pub view missing: Gd<Button>,
+        #[cfg(any())]
+        view row: VBoxContainer {
+            pub view nested: Gd<Button>,
+        },
+        #[var]
+        pub count: i32,
+    }
+}
+
+viewtype! {
+    #[class(init)]
+    pub struct Counter: VBoxContainer {
+        #[var]
+        pub count: i32,
+    }
+}
+
+fn menu_init(button: Gd<Button>) -> Menu_Init {
+    Menu_Init { button, count: 0 }
+}
+
+fn is_view<T: View>() {}
+
+#[test]
+fn generic_enums_are_views() {
+    is_view::<Either<Gd<Button>, Gd<Label>>>();
+    is_view::<Either<Either<Gd<Button>, Gd<Label>>, Option<Gd<Label>>>>();
+    is_view::<Labelled<String>>();
+}
+
+#[test]
+fn enums_can_have_unit_and_multi_field_variants() {
+    is_view::<Field>();
+    is_view::<Nothing>();
+}
+
+#[test]
+fn attributes_are_forwarded() {
+    is_view::<Documented>();
+    let _ = format!("{:?}", Documented::Empty.clone());
+    let _: fn(Gd<Button>) -> Menu_Init = menu_init;
+}
@@ -0,0 +1,154 @@
+use moonstone::{
+    App, ItemExtent, Viewport, VirtualList, fragment,
+    mock::{Leaf, Mock, MockNode, leaf},
+    reactive::Signal,
+};
+
+type List = VirtualList<fn(usize) -> (usize, Leaf), fn(f32) -> Leaf>;
+
+fn item(idx: usize) -> (usize, Leaf) {
+    (idx, leaf(format!("item {idx}")))
+}
+
+fn spacer(extent: f32) -> Leaf {
+    leaf(format!("spacer {extent}"))
+}
+
+fn list(len: usize, extent: ItemExtent, viewport: &Signal<Viewport>) -> List {
+    VirtualList::new(len, extent, viewport.clone(), spacer as _, item as _)
+}
+
+#[test]
+fn only_visible_items_are_built() {
+    let viewport = Signal::new(Viewport {
+        offset: 0.0,
+        extent: 30.0,
+    });
+    let root = MockNode::new("Root", "root");
+    let app = App::<_, Mock>::attach(root.clone(), || {
+        list(10_000, ItemExtent::Fixed(10.0), &viewport).overscan(1)
+    });
+    assert_eq!(
+        root.child_names(),
+        [
+            "spacer 0",
+            "item 0",
+            "item 1",
+            "item 2",
+            "item 3",
+            "spacer 99960"
+        ]
+    );
+
+    viewport.set(Viewport {
+        offset: 5000.0,
+        extent: 30.0,
+    });
+    assert_eq!(
+        root.child_names(),
+        [
+            "spacer 4990",
+            "item 499",
+            "item 500",
+            "item 501",
+            "item 502",
+            "item 503",
+            "spacer 94960"
+        ]
+    );
+
+    app.destroy();
+    assert!(root.children().is_empty());
+}
+
+#[test]
+fn items_that_stay_visible_keep_their_nodes() {
+    let viewport = Signal::new(Viewport {
+        offset: 0.0,
+        extent: 30.0,
+    });
+    let root = MockNode::new("Root", "root");
+    let _app = App::<_, Mock>::attach(root.clone(), || {
+        list(100, ItemExtent::Fixed(10.0), &viewport).overscan(0)
+    });
+    let second = root.children()[2].clone();
+
+    viewport.set(Viewport {
+        offset: 10.0,
+        extent: 30.0,
+    });
+    assert_eq!(
+        root.child_names(),
+        ["spacer 10", "item 1", "item 2", "item 3", "spacer 960"]
+    );
+    assert_eq!(root.children()[1], second);
+}
+
+#[test]
+fn measured_items_are_laid_out_by_extent() {
+    let viewport = Signal::new(Viewport {
+        offset: 40.0,
+        extent: 40.0,
+    });
+    let extent = ItemExtent::measure(|idx| if idx % 2 == 0 { 10.0 } else { 30.0 });
+    let root = MockNode::new("Root", "root");
+    let _app = App::<_, Mock>::attach(root.clone(), || list(10, extent, &viewport).overscan(0));
+    assert_eq!(
+        root.child_names(),
+        ["spacer 40", "item 2", "item 3", "spacer 120"]
+    );
+}
+
+#[test]
+fn rebuilding_the_parent_updates_the_items() {
+    let viewport = Signal::new(Viewport {
+        offset: 0.0,
+        extent: 20.0,
+    });
+    let root = MockNode::new("Root", "root");
+    let mut app = App::<_, Mock>::attach(root.clone(), || {
+        list(100, ItemExtent::Fixed(10.0), &viewport).overscan(0)
+    });
+
+    app.update(|list| *list = self::list(1, ItemExtent::Fixed(10.0), &viewport).overscan(0));
+    assert_eq!(root.child_names(), ["spacer 0", "item 0", "spacer 0"]);
+
+    // The new list follows the viewport too.
+    app.update(|list| *list = self::list(100, ItemExtent::Fixed(10.0), &viewport).overscan(0));
+    viewport.set(Viewport {
+        offset: 100.0,
+        extent: 20.0,
+    });
+    assert_eq!(
+        root.child_names(),
+        ["spacer 100", "item 10", "item 11", "spacer 880"]
+    );
+}
+
+#[test]
+fn scrolling_keeps_the_anchor_strategy() {
+    let viewport = Signal::new(Viewport {
+        offset: 0.0,
+        extent: 10.0,
+    });
+    let root = MockNode::new("Root", "root");
+    let _app = App::<_, Mock>::attach(root.clone(), || {
+        fragment(
+            VirtualList::new(
+                100,
+                ItemExtent::Fixed(10.0),
+                viewport.clone(),
+                spacer,
+                |idx| (idx, Some(leaf(format!("item {idx}")))),
+            )
+            .overscan(0),
+        )
+    });
+
+    viewport.set(Viewport {
+        offset: 50.0,
+        extent: 10.0,
+    });
+    assert_eq!(root.child_names(), ["spacer 50", "item 5", "spacer 940"]);
+    assert!(root.children().iter().all(|c| !c.is_marker()));
+}
@@ -1,15 +1,6 @@
 mod viewtype;
 
-use heck::ToUpperCamelCase;
-use proc_macro::TokenStream;
-use quote::{format_ident, quote};
-use syn::{
-    Attribute, Data, DeriveInput, Field, Fields, ItemStruct, Lit, LitInt, Meta, MetaNameValue,
-    Token,
-    parse::{Parse, ParseStream},
-    parse_macro_input,
-    punctuated::Punctuated,
-};
+use syn::parse_macro_input;
 
 use crate::viewtype::ViewDef;
 
@@ -18,7 +9,9 @@ use crate::viewtype::ViewDef;
 pub fn viewtype(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let b = parse_macro_input!(item as ViewDef);
 
-    b.gen_rust().into()
+    b.gen_rust()
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
 
 // #[proc_macro_attribute]
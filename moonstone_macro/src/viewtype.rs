@@ -1,11 +1,10 @@
-use std::time::Instant;
+use std::collections::HashSet;
 
-use heck::ToUpperCamelCase;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use syn::{
-    AngleBracketedGenericArguments, Expr, Ident, Pat, Path, Token, Type, Visibility, braced,
-    bracketed, parenthesized, parse::Parse, parse_quote, punctuated::Punctuated, token,
+    Attribute, Fields, Generics, Ident, Meta, Token, Type, Visibility, WhereClause, braced,
+    parse::Parse, parse_quote, punctuated::Punctuated, token,
 };
 
 mod kw {
@@ -13,6 +12,7 @@ mod kw {
 }
 
 pub struct ViewDef {
+    attrs: Vec<Attribute>,
     vis: Visibility,
     typ: ViewType,
 }
@@ -24,15 +24,19 @@ pub enum ViewType {
     },
     Enum {
         name: Ident,
+        generics: Generics,
         variants: Punctuated<ViewVariant, Token![,]>,
     },
 }
 
 pub struct ViewVariant {
+    attrs: Vec<Attribute>,
     name: Ident,
-    typ: Type,
+    /// Each field is a view, built in order.
+    fields: Fields,
 }
 pub struct ViewField {
+    attrs: Vec<Attribute>,
     vis: Visibility,
     view: Option<kw::view>,
     name: Ident,
@@ -42,47 +46,81 @@ pub struct ViewField {
 
 impl Parse for ViewDef {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
         let vis = input.parse()?;
-        if input.peek(Token![struct]) {
+        let lookahead = input.lookahead1();
+        if lookahead.peek(Token![struct]) {
             input.parse::<Token![struct]>()?;
-            let name = input.parse()?;
+            let name = input.parse::<Ident>()?;
+            let mut generics = input.parse::<Generics>()?;
+            if !input.peek(Token![:]) {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "viewtype structs need a base class, as in `struct Name: Node { ... }`",
+                ));
+            }
             input.parse::<Token![:]>()?;
             let base = input.parse()?;
+            generics.where_clause = input.parse()?;
+            if !generics.params.is_empty() || generics.where_clause.is_some() {
+                return Err(syn::Error::new_spanned(
+                    generics,
+                    "viewtype structs can't be generic, since Godot classes can't be",
+                ));
+            }
             let inner;
             braced!(inner in input);
             let body = Punctuated::parse_terminated(&inner)?;
             Ok(ViewDef {
+                attrs,
                 vis,
                 typ: ViewType::Struct { name, base, body },
             })
-        } else if input.peek(Token![enum]) {
+        } else if lookahead.peek(Token![enum]) {
             input.parse::<Token![enum]>()?;
             let name = input.parse()?;
+            let mut generics = input.parse::<Generics>()?;
+            generics.where_clause = input.parse::<Option<WhereClause>>()?;
             let inner;
             braced!(inner in input);
             let variants = Punctuated::parse_terminated(&inner)?;
             Ok(ViewDef {
+                attrs,
                 vis,
-                typ: ViewType::Enum { name, variants },
+                typ: ViewType::Enum {
+                    name,
+                    generics,
+                    variants,
+                },
             })
         } else {
-            panic!("Struct or enum bro")
+            Err(lookahead.error())
         }
     }
 }
 
 impl Parse for ViewVariant {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
         let name = input.parse()?;
-        let inner;
-        parenthesized!(inner in input);
-        let typ = inner.parse()?;
-        Ok(ViewVariant { name, typ })
+        let fields = if input.peek(token::Paren) {
+            Fields::Unnamed(input.parse()?)
+        } else if input.peek(token::Brace) {
+            Fields::Named(input.parse()?)
+        } else {
+            Fields::Unit
+        };
+        Ok(ViewVariant {
+            attrs,
+            name,
+            fields,
+        })
     }
 }
 
 impl Parse for ViewField {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
         let vis = input.parse()?;
         let view = if input.peek(kw::view) {
             Some(input.parse::<kw::view>()?)
@@ -100,6 +138,7 @@ impl Parse for ViewField {
             None
         };
         Ok(ViewField {
+            attrs,
             vis,
             view,
             name,
@@ -114,33 +153,161 @@ struct DataCollect {
     pub view_struct_fields: TokenStream,
     pub build_view_values: TokenStream,
     pub build_fields: TokenStream,
+    pub flush_fields: TokenStream,
     pub impls: TokenStream,
 }
 
-fn collect_data(list: &Punctuated<ViewField, Token![,]>, data: &mut DataCollect) {
+/// The attributes of an item, field or variant, split by where they end up.
+struct Attrs<'a> {
+    /// Put on everything generated for it.
+    cfgs: Vec<&'a Attribute>,
+    docs: Vec<&'a Attribute>,
+    other: Vec<&'a Attribute>,
+}
+impl<'a> Attrs<'a> {
+    fn split(attrs: &'a [Attribute]) -> Self {
+        let mut out = Attrs {
+            cfgs: vec![],
+            docs: vec![],
+            other: vec![],
+        };
+        for attr in attrs {
+            if attr.path().is_ident("cfg") {
+                out.cfgs.push(attr);
+            } else if attr.path().is_ident("doc") {
+                out.docs.push(attr);
+            } else {
+                out.other.push(attr);
+            }
+        }
+        out
+    }
+
+    /// Fails on the first attribute that isn't a doc comment or `#[cfg]`.
+    fn only_docs_and_cfgs(&self, what: &str) -> syn::Result<()> {
+        match self.other.first() {
+            Some(attr) => Err(syn::Error::new_spanned(
+                attr,
+                format!("only doc comments and `#[cfg]` can be put on {what}"),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The `#[class]` attribute of a generated Godot class, with the options of
+/// the `#[class]` attributes in `attrs` added to `base` and `no_init`.
+///
+/// `no_init` is left out if `init` is given, which is only allowed if the
+/// class has no `view` fields, since those can't be default-constructed.
+fn class_attr(base: &Type, attrs: &[&Attribute], has_views: bool) -> syn::Result<TokenStream> {
+    let mut options = vec![quote! { base=#base }];
+    let mut init = false;
+    for attr in attrs {
+        let metas = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+        for meta in metas {
+            if meta.path().is_ident("base") {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "the base class goes after the struct name, as in `struct Name: Node { ... }`",
+                ));
+            }
+            if meta.path().is_ident("no_init") {
+                continue;
+            }
+            if meta.path().is_ident("init") {
+                if has_views {
+                    return Err(syn::Error::new_spanned(
+                        meta,
+                        "`init` can't be used on a viewtype with `view` fields, since they have no default value; build it through its `_Init` struct instead",
+                    ));
+                }
+                init = true;
+            }
+            options.push(quote! { #meta });
+        }
+    }
+    if !init {
+        options.push(quote! { no_init });
+    }
+    Ok(quote! { #[class(#(#options),*)] })
+}
+
+/// Checks that `name` can be a field of the generated class, and that no other
+/// field in `seen` has it.
+fn check_field_name(name: &Ident, seen: &mut HashSet<Ident>) -> syn::Result<()> {
+    if name == "base" {
+        return Err(syn::Error::new_spanned(
+            name,
+            "`base` is reserved for the `Base` field of the Godot class, use another name",
+        ));
+    }
+    if name.to_string().starts_with("__") {
+        return Err(syn::Error::new_spanned(
+            name,
+            "names starting with `__` are reserved for generated code, use another name",
+        ));
+    }
+    if let Some(first) = seen.get(name) {
+        let mut err = syn::Error::new_spanned(name, format!("duplicate field `{name}`"));
+        err.combine(syn::Error::new_spanned(first, "first defined here"));
+        return Err(err);
+    }
+    seen.insert(name.clone());
+    Ok(())
+}
+
+/// Collects the code for the fields in `list`, and the ones nested in them.
+///
+/// `outer_cfgs` are the `#[cfg]`s of the field `list` is the body of, if any.
+fn collect_data(
+    list: &Punctuated<ViewField, Token![,]>,
+    outer_cfgs: &[&Attribute],
+    seen: &mut HashSet<Ident>,
+    data: &mut DataCollect,
+) -> syn::Result<()> {
     for field in list {
         let vis = &field.vis;
         let name = &field.name;
+        check_field_name(name, seen)?;
+        let attrs = Attrs::split(&field.attrs);
+        let cfgs = outer_cfgs
+            .iter()
+            .chain(&attrs.cfgs)
+            .copied()
+            .collect::<Vec<_>>();
+        let docs = &attrs.docs;
+        let other = &attrs.other;
         let priv_name = format_ident!("__DONT_USE_THIS_DIRECTLY_{}", name);
 
         let typ = &field.typ;
         match (field.view, &field.body) {
             (Some(v), None) => {
+                attrs.only_docs_and_cfgs("`view` fields")?;
                 let kw = Ident::new("try", v.span);
                 // if *name != "__" {
-                data.init_struct_fields.extend(quote! { #vis #name: #typ, });
+                data.init_struct_fields
+                    .extend(quote! { #(#cfgs)* #(#docs)* #vis #name: #typ, });
                 data.view_struct_fields
-                    .extend(quote! { #vis #priv_name: ::moonstone::ViewValue<#typ>, });
+                    .extend(quote! { #(#cfgs)* #vis #priv_name: ::moonstone::ViewValue<#typ>, });
 
                 data.build_view_values.extend(quote! {
-                    stringify!(#kw);
-                    let __state = <#typ as ::moonstone::View>::build(&self.#name, &mut __parent);
-                    let #name = ::moonstone::ViewValue::__create(self.#name, __state);
+                    #(#cfgs)*
+                    let #name = {
+                        stringify!(#kw);
+                        let __state = <#typ as ::moonstone::View>::build(&self.#name, &mut __parent);
+                        ::moonstone::ViewValue::__create(self.#name, __state)
+                    };
                 });
                 data.build_fields.extend(quote! {
-                    #priv_name: #name,
+                    #(#cfgs)* #priv_name: #name,
+                });
+                data.flush_fields.extend(quote! {
+                    #(#cfgs)* self.#priv_name.__flush();
                 });
                 data.impls.extend(quote! {
+                    #(#cfgs)*
+                    #(#docs)*
                     #vis fn #name<'a>(&'a self) -> <#typ as ::moonstone::View>::Access<'a> {
                         <#typ as ::moonstone::View>::access(self.#priv_name.__value())
                     }
@@ -149,41 +316,58 @@ fn collect_data(list: &Punctuated<ViewField, Token![,]>, data: &mut DataCollect)
             }
             (None, None) => {
                 // if *name != "__" {
-                data.init_struct_fields.extend(quote! { #vis #name: #typ, });
-                data.view_struct_fields.extend(quote! { #vis #name: #typ, });
+                data.init_struct_fields
+                    .extend(quote! { #(#cfgs)* #(#docs)* #vis #name: #typ, });
+                data.view_struct_fields
+                    .extend(quote! { #(#cfgs)* #(#docs)* #(#other)* #vis #name: #typ, });
                 data.build_fields.extend(quote! {
-                    #name: self.#name,
+                    #(#cfgs)* #name: self.#name,
                 });
                 // }
             }
             (kw, Some(body)) => {
                 let Some(kw) = kw else {
-                    panic!("Bruuuhhh put view there");
+                    return Err(syn::Error::new_spanned(
+                        name,
+                        format!(
+                            "`{name}` has a body, so it is a node built by the view and needs to be marked with `view`, as in `view {name}: ...`"
+                        ),
+                    ));
                 };
+                attrs.only_docs_and_cfgs("`view` fields")?;
                 let kw = Ident::new("try", kw.span);
 
                 data.init_struct_fields
-                    .extend(quote! { #vis #name: ::godot::obj::Gd<#typ>, });
+                    .extend(quote! { #(#cfgs)* #(#docs)* #vis #name: ::godot::obj::Gd<#typ>, });
                 data.view_struct_fields
-                    .extend(quote! { #vis #priv_name: ::godot::obj::Gd<#typ>, });
+                    .extend(quote! { #(#cfgs)* #vis #priv_name: ::godot::obj::Gd<#typ>, });
 
+                let outer_parent = format_ident!("__parent_of_{}", name);
                 data.build_view_values.extend(quote! {
-                    stringify!(#kw);
-                    __parent.node().add_child(&self.#name);
-                    let mut __parent = ::moonstone::ChildAnchor::new(self.#name.clone().upcast());
+                    #(#cfgs)*
+                    let #outer_parent = {
+                        stringify!(#kw);
+                        __parent.add(&self.#name.clone().upcast());
+                        ::std::mem::replace(
+                            &mut __parent,
+                            ::moonstone::ChildAnchor::new(self.#name.clone().upcast()),
+                        )
+                    };
                 });
                 // if *name != "__" {
                 data.build_fields.extend(quote! {
-                    #priv_name: self.#name,
+                    #(#cfgs)* #priv_name: self.#name,
                 });
                 // }
 
-                collect_data(body, data);
+                collect_data(body, &cfgs, seen, data)?;
 
                 data.build_view_values.extend(quote! {
-                    let mut __parent = ::moonstone::ChildAnchor::new(__parent.node().get_parent().unwrap());
+                    #(#cfgs)* { __parent = #outer_parent; }
                 });
                 data.impls.extend(quote! {
+                    #(#cfgs)*
+                    #(#docs)*
                     #vis fn #name(&self) -> ::godot::obj::Gd<#typ> {
                         self.#priv_name.clone()
                     }
@@ -191,54 +375,71 @@ fn collect_data(list: &Punctuated<ViewField, Token![,]>, data: &mut DataCollect)
             }
         };
     }
+    Ok(())
 }
 
 impl ViewDef {
-    pub fn gen_rust(&self) -> TokenStream {
+    pub fn gen_rust(&self) -> syn::Result<TokenStream> {
         let vis = &self.vis;
-        match &self.typ {
+        let attrs = Attrs::split(&self.attrs);
+        let cfgs = &attrs.cfgs;
+        Ok(match &self.typ {
             ViewType::Struct { name, base, body } => {
                 let mut collect = DataCollect {
                     init_struct_fields: quote! {},
                     view_struct_fields: quote! {},
                     build_view_values: quote! {},
                     build_fields: quote! {},
+                    flush_fields: quote! {},
                     impls: quote! {},
                 };
 
-                collect_data(body, &mut collect);
+                collect_data(body, &[], &mut HashSet::new(), &mut collect)?;
+
+                let (class_attrs, other): (Vec<_>, Vec<_>) = attrs
+                    .other
+                    .iter()
+                    .partition(|attr| attr.path().is_ident("class"));
+                let has_views = body.iter().any(|field| field.view.is_some());
+                let class_attr = class_attr(base, &class_attrs, has_views)?;
+                let docs = &attrs.docs;
 
-                let mod_name = format_ident!("_mod_{}", name);
                 let init_struct_name = format_ident!("{}_Init", name);
                 let DataCollect {
                     init_struct_fields,
                     view_struct_fields,
                     build_view_values,
                     build_fields,
+                    flush_fields,
                     impls,
                 } = collect;
 
                 quote! {
 
+                    #(#cfgs)*
+                    #(#docs)*
                     #[derive(::godot::prelude::GodotClass)]
-                    #[class(base=#base, no_init)]
+                    #class_attr
+                    #(#other)*
                     #[allow(non_snake_case)]
                     #vis struct #name {
                         base: ::godot::obj::Base<#base>,
                         #view_struct_fields
                     }
+                    #(#cfgs)*
                     #[allow(non_camel_case_types)]
                     #vis struct #init_struct_name {
                         #init_struct_fields
                     }
 
+                    #(#cfgs)*
                     impl #init_struct_name {
                         pub fn build(self, f: impl FnOnce(&mut ::godot::obj::Gd<#name>)) -> ::godot::obj::Gd<#name> {
                             use ::moonstone::Anchor;
                             use ::godot::obj::NewAlloc;
                             let mut out = ::godot::obj::Gd::from_init_fn(|__base: ::godot::obj::Base<#base>| {
                                 let mut __node = __base.to_init_gd();
-                                let mut __parent = ::moonstone::ChildAnchor::new(__node.upcast());
+                                let mut __parent = ::moonstone::ChildAnchor::<::moonstone::Godot>::new(__node.upcast());
                                 #build_view_values
                                 #name {
                                     base: __base,
@@ -251,14 +452,38 @@ impl ViewDef {
                             out
                         }
                     }
+                    #(#cfgs)*
                     impl #name {
                         #impls
                     }
+                    #(#cfgs)*
+                    impl ::moonstone::scheduler::Flush for #name {
+                        fn __flush(&mut self) {
+                            #flush_fields
+                        }
+                    }
                 }
             }
-            ViewType::Enum { name, variants } => {
+            ViewType::Enum {
+                name,
+                generics,
+                variants,
+            } => {
                 let view_state_name = format_ident!("__{}_ViewStateType", name);
 
+                // Both the state enum and the `View` impl need every variant to
+                // be a view.
+                let mut view_generics = generics.clone();
+                let predicates = &mut view_generics.make_where_clause().predicates;
+                for field in variants.iter().flat_map(|i| &i.fields) {
+                    let typ = &field.ty;
+                    predicates.push(parse_quote!(#typ: ::moonstone::View));
+                }
+                let (impl_generics, ty_generics, view_where_clause) =
+                    view_generics.split_for_impl();
+                let where_clause = &generics.where_clause;
+                let item_attrs = &self.attrs;
+
                 let mut variant_gen = quote! {};
                 let mut view_state_variant_gen = quote! {};
                 let mut build_match = quote! {};
@@ -267,47 +492,96 @@ impl ViewDef {
                 let mut collect_match = quote! {};
                 for i in variants {
                     let variant = &i.name;
-                    let typ = &i.typ;
-                    variant_gen.extend(quote! { #variant(#typ), });
-                    view_state_variant_gen
-                        .extend(quote! { #variant(<#typ as ::moonstone::View>::State), });
+                    let variant_attrs = &i.attrs;
+                    let variant_cfgs = Attrs::split(&i.attrs).cfgs;
+                    let fields = &i.fields;
+                    let types = fields.iter().map(|field| &field.ty).collect::<Vec<_>>();
+                    let values = (0..types.len())
+                        .map(|idx| format_ident!("__field{}", idx))
+                        .collect::<Vec<_>>();
+                    let states = (0..types.len())
+                        .map(|idx| format_ident!("__state{}", idx))
+                        .collect::<Vec<_>>();
+                    // The state of a field only exists if the field does.
+                    let field_cfgs = fields
+                        .iter()
+                        .map(|field| {
+                            let cfgs = Attrs::split(&field.attrs).cfgs;
+                            match (&field.ident, cfgs.first()) {
+                                (None, Some(cfg)) => Err(syn::Error::new_spanned(
+                                    cfg,
+                                    "`#[cfg]` can only be put on named fields of variants",
+                                )),
+                                _ => Ok(quote! { #(#cfgs)* }),
+                            }
+                        })
+                        .collect::<syn::Result<Vec<_>>>()?;
+
+                    let value_pat = match fields {
+                        Fields::Unit => quote! { #name::#variant },
+                        Fields::Unnamed(_) => quote! { #name::#variant(#(#values),*) },
+                        Fields::Named(_) => {
+                            let field_names = fields.iter().map(|field| &field.ident);
+                            quote! {
+                                #name::#variant { #(#field_cfgs #field_names: #values),* }
+                            }
+                        }
+                    };
+                    let state_pat =
+                        quote! { #view_state_name::#variant { #(#field_cfgs #states,)* } };
+
+                    variant_gen.extend(quote! { #(#variant_attrs)* #variant #fields, });
+                    view_state_variant_gen.extend(quote! {
+                        #(#variant_cfgs)*
+                        #variant { #(#field_cfgs #states: <#types as ::moonstone::View>::State,)* },
+                    });
                     build_match.extend(quote! {
-                        #name::#variant(v) => #view_state_name::#variant(v.build(enum_anchor)),
+                        #(#variant_cfgs)*
+                        #value_pat => #view_state_name::#variant {
+                            #(#field_cfgs #states: #values.build(enum_anchor),)*
+                        },
                     });
                     rebuild_match.extend(quote! {
-                        (#name::#variant(new), #view_state_name::#variant(inner_state)) => {
-                            new.rebuild(inner_state);
+                        #(#variant_cfgs)*
+                        (#value_pat, #state_pat) => {
+                            #(#field_cfgs #values.rebuild(#states);)*
                             return;
                         },
                     });
                     teardown_match.extend(quote! {
-                        #view_state_name::#variant(inner_state) => {
-                            <#typ as ::moonstone::View>::teardown(inner_state, enum_anchor);
+                        #(#variant_cfgs)*
+                        #state_pat => {
+                            #(#field_cfgs <#types as ::moonstone::View>::teardown(#states, enum_anchor);)*
                         },
                     });
                     collect_match.extend(quote! {
-                        #view_state_name::#variant(inner_state) => {
-                            <#typ as ::moonstone::View>::collect_nodes(inner_state, nodes);
+                        #(#variant_cfgs)*
+                        #state_pat => {
+                            #(#field_cfgs <#types as ::moonstone::View>::collect_nodes(#states, nodes);)*
                         },
                     });
                 }
                 quote! {
-                    #vis enum #name {
+                    #(#item_attrs)*
+                    #vis enum #name #generics #where_clause {
                         #variant_gen
                     }
+                    #(#cfgs)*
                     #[allow(non_camel_case_types)]
-                    #vis enum #view_state_name {
+                    #vis enum #view_state_name #generics #view_where_clause {
                         #view_state_variant_gen
                     }
-                    impl ::moonstone::View for #name {
-                        type State = (::moonstone::BeforeAnchor, #view_state_name);
+                    // Enums with only unit variants never use the anchor.
+                    #(#cfgs)*
+                    #[allow(unused_variables)]
+                    impl #impl_generics ::moonstone::View for #name #ty_generics #view_where_clause {
+                        type State = (::moonstone::BeforeAnchor, #view_state_name #ty_generics);
                         type Access<'a> = &'a Self where Self: 'a;
 
                         fn build(&self, parent_anchor: &mut dyn ::moonstone::Anchor) -> Self::State {
                             use ::moonstone::Anchor;
-                            let mut enum_anchor_owned = <::moonstone::BeforeAnchor as ::moonstone::Anchor>::new(<::godot::classes::Node as ::godot::obj::NewAlloc>::new_alloc());
+                            let mut enum_anchor_owned = ::moonstone::BeforeAnchor::attach(parent_anchor);
                             let enum_anchor = &mut enum_anchor_owned;
-                            parent_anchor.add(&enum_anchor.node());
 
                             let inner_state = match self {
                                 #build_match
@@ -341,16 +615,15 @@ impl ViewDef {
                             match &mut state.1 {
                                 #teardown_match
                             }
-                            parent_anchor.remove(&state.0.node());
-                            state.0.node().queue_free();
+                            state.0.detach(parent_anchor);
                         }
 
                         fn collect_nodes(state: &Self::State, nodes: &mut Vec<::godot::obj::Gd<::godot::classes::Node>>) {
                             use ::moonstone::Anchor;
-                            nodes.push(state.0.node());
                             match &state.1 {
                                 #collect_match
                             }
+                            state.0.collect_nodes(nodes);
                         }
 
                         fn access<'a>(&'a self) -> Self::Access<'a> {
@@ -359,6 +632,6 @@ impl ViewDef {
                     }
                 }
             }
-        }
+        })
     }
 }